@@ -0,0 +1,17 @@
+/// Xorshift pseudo random generator, so that the synthetic bench inputs are
+/// the same on every run.
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new() -> Self {
+        XorShift(0x2024)
+    }
+
+    /// Next number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
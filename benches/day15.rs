@@ -0,0 +1,69 @@
+mod common;
+
+use aoc_2024::day15::{parse_moves, Warehouse};
+use common::XorShift;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Square warehouse surrounded by walls, with a box on most tiles of every
+/// other row and a few inner walls.
+fn warehouse_input(size: usize) -> String {
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| {
+                    if i == 0 || j == 0 || i == size - 1 || j == size - 1 {
+                        '#'
+                    } else if i == size / 2 && j == size / 2 {
+                        '@'
+                    } else if (i * 31 + j * 17) % 97 == 0 {
+                        '#'
+                    } else if i % 2 == 0 && j % 3 != 0 {
+                        'O'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Pseudo random sequence of moves, split on lines of 1000 moves
+fn moves_input(n_moves: usize) -> String {
+    let mut rng = XorShift::new();
+    (0..n_moves)
+        .map(|i| {
+            let c = ['<', '>', '^', 'v'][rng.below(4)];
+            if i % 1000 == 999 {
+                format!("{}\n", c)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+fn bench_warehouse(c: &mut Criterion) {
+    let grid_input = warehouse_input(1000);
+    let moves = parse_moves(&moves_input(100_000));
+    for box_width in [1, 2] {
+        let warehouse = Warehouse::new(&grid_input, box_width);
+        c.bench_function(&format!("day15 1000x1000 width {}", box_width), |b| {
+            b.iter(|| {
+                let mut warehouse = warehouse.clone();
+                for mv in moves.iter() {
+                    warehouse.step(mv);
+                }
+                warehouse.gps()
+            })
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_warehouse
+}
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+mod common;
+
+use aoc_2024::day16::{astar, dijkstra, parse, CostModel};
+use aoc_2024::grid::grid_to_string;
+use common::XorShift;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Open maze with a regular pattern of pillars, S bottom left and E top right
+fn pillars_maze(size: usize) -> String {
+    let mut grid = vec![vec!['.'; size]; size];
+    for (i, row) in grid.iter_mut().enumerate() {
+        for (j, tile) in row.iter_mut().enumerate() {
+            if i == 0 || j == 0 || i == size - 1 || j == size - 1 || (i % 4 == 2 && j % 6 == 3) {
+                *tile = '#';
+            }
+        }
+    }
+    grid[size - 2][1] = 'S';
+    grid[1][size - 2] = 'E';
+    grid_to_string(&grid)
+}
+
+/// Pseudo random maze like the real input: corridors carved by a depth-first
+/// walk, with some extra walls knocked down to make loops.
+fn carved_maze(size: usize) -> String {
+    let mut rng = XorShift::new();
+    let mut grid = vec![vec!['#'; size]; size];
+    let mut stack = vec![(size - 2, 1)];
+    grid[size - 2][1] = '.';
+    while let Some(&(i, j)) = stack.last() {
+        let neighbors: Vec<(usize, usize)> = [(0, 2), (2, 0), (0, -2), (-2, 0)]
+            .iter()
+            .map(|(di, dj)| ((i as isize + di) as usize, (j as isize + dj) as usize))
+            .filter(|(ni, nj)| *ni > 0 && *nj > 0 && *ni < size - 1 && *nj < size - 1)
+            .filter(|(ni, nj)| grid[*ni][*nj] == '#')
+            .collect();
+        if neighbors.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (ni, nj) = neighbors[rng.below(neighbors.len())];
+        grid[(i + ni) / 2][(j + nj) / 2] = '.';
+        grid[ni][nj] = '.';
+        stack.push((ni, nj));
+    }
+    for _ in 0..size * size / 50 {
+        let (i, j) = (1 + rng.below(size - 2), 1 + rng.below(size - 2));
+        grid[i][j] = '.';
+    }
+    grid[size - 2][1] = 'S';
+    grid[1][size - 2] = 'E';
+    grid_to_string(&grid)
+}
+
+fn bench_searches(c: &mut Criterion) {
+    let costs = CostModel::default();
+    let mazes = [
+        ("carved 141", carved_maze(141)),
+        ("carved 501", carved_maze(501)),
+        ("pillars 141", pillars_maze(141)),
+        ("pillars 501", pillars_maze(501)),
+    ];
+    for (name, maze) in mazes.iter() {
+        let (grid, start, end) = parse(maze);
+        let by_dijkstra = dijkstra(&grid, &start, &end, &costs);
+        let by_astar = astar(&grid, &start, &end, &costs);
+        assert_eq!(by_dijkstra.score, by_astar.score);
+        println!(
+            "day16 {}: score {:?}, dijkstra expands {} states, astar {}",
+            name, by_dijkstra.score, by_dijkstra.expanded, by_astar.expanded
+        );
+        c.bench_function(&format!("day16 dijkstra {}", name), |b| {
+            b.iter(|| dijkstra(&grid, &start, &end, &costs))
+        });
+        c.bench_function(&format!("day16 astar {}", name), |b| {
+            b.iter(|| astar(&grid, &start, &end, &costs))
+        });
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_searches
+}
+criterion_main!(benches);
@@ -0,0 +1,115 @@
+mod common;
+
+use aoc_2024::day19::{parse, part1, part2, part2_u128, Towels};
+use common::XorShift;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::{HashMap, HashSet};
+
+/// Pseudo random towels and designs, about the size of the real input
+fn towels_input(n_patterns: usize, n_designs: usize) -> String {
+    let mut rng = XorShift::new();
+    let colours = ['w', 'u', 'b', 'r', 'g'];
+    let patterns: Vec<String> = (0..n_patterns)
+        .map(|_| {
+            (0..1 + rng.below(8))
+                .map(|_| colours[rng.below(5)])
+                .collect()
+        })
+        .collect();
+    let designs: Vec<String> = (0..n_designs)
+        .map(|_| {
+            (0..40 + rng.below(20))
+                .map(|_| colours[rng.below(5)])
+                .collect()
+        })
+        .collect();
+    format!("{}\n\n{}", patterns.join(", "), designs.join("\n"))
+}
+
+/// Recursive implementation the trie replaced, with a cache keyed on suffixes
+mod recursive {
+    use super::*;
+
+    fn can_make(
+        design: &[usize],
+        patterns: &HashSet<Vec<usize>>,
+        cache: &mut HashMap<Vec<usize>, bool>,
+    ) -> bool {
+        if design.is_empty() {
+            return true;
+        }
+        if let Some(&result) = cache.get(design) {
+            return result;
+        }
+
+        let result = patterns
+            .iter()
+            .filter(|&pattern| design.starts_with(pattern))
+            .any(|pattern| can_make(&design[pattern.len()..], patterns, cache));
+
+        cache.insert(design.to_vec(), result);
+        result
+    }
+
+    pub fn part1(towels: &Towels) -> usize {
+        let mut cache: HashMap<Vec<usize>, bool> = HashMap::new();
+        towels
+            .designs
+            .iter()
+            .filter(|design| can_make(design, &towels.patterns, &mut cache))
+            .count()
+    }
+
+    fn count_ways(
+        design: &[usize],
+        patterns: &HashSet<Vec<usize>>,
+        cache: &mut HashMap<Vec<usize>, u128>,
+    ) -> u128 {
+        if design.is_empty() {
+            return 1;
+        }
+        if let Some(&count) = cache.get(design) {
+            return count;
+        }
+
+        let count = patterns
+            .iter()
+            .filter(|&pattern| design.starts_with(pattern))
+            .map(|pattern| count_ways(&design[pattern.len()..], patterns, cache))
+            .sum();
+
+        cache.insert(design.to_vec(), count);
+        count
+    }
+
+    pub fn part2(towels: &Towels) -> u128 {
+        let mut cache: HashMap<Vec<usize>, u128> = HashMap::new();
+        towels
+            .designs
+            .iter()
+            .map(|design| count_ways(design, &towels.patterns, &mut cache))
+            .sum()
+    }
+}
+
+fn bench_towels(c: &mut Criterion) {
+    let input = parse(&towels_input(450, 400)).unwrap();
+    assert_eq!(part1(&input), recursive::part1(&input));
+    assert_eq!(part2_u128(&input), Ok(recursive::part2(&input)));
+    c.bench_function("day19 part1 recursive", |b| {
+        b.iter(|| recursive::part1(&input))
+    });
+    c.bench_function("day19 part1 trie", |b| b.iter(|| part1(&input)));
+    c.bench_function("day19 part2 recursive", |b| {
+        b.iter(|| recursive::part2(&input))
+    });
+    c.bench_function("day19 part2 trie", |b| b.iter(|| part2(&input)));
+    c.bench_function("day19 part2 trie u128", |b| b.iter(|| part2_u128(&input)));
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_towels
+}
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+use aoc_2024::day20::{parse, solve};
+use aoc_2024::grid::grid_to_string;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// A winding race track the size of the real input: rows going back and
+/// forth, one wall apart, so that many cheats cut through them.
+fn track_input(size: usize) -> String {
+    let mut grid = vec![vec!['#'; size]; size];
+    let n_rows = (size - 1) / 2;
+    for r in 0..n_rows {
+        let i = 2 * r + 1;
+        for cell in grid[i][1..size - 1].iter_mut() {
+            *cell = '.';
+        }
+        // link to the next row, alternating sides
+        if r + 1 < n_rows {
+            let j = if r.is_multiple_of(2) { size - 2 } else { 1 };
+            grid[i + 1][j] = '.';
+        }
+    }
+    let last_row = 2 * n_rows - 1;
+    grid[1][1] = 'S';
+    grid[last_row][if n_rows.is_multiple_of(2) {
+        1
+    } else {
+        size - 2
+    }] = 'E';
+    grid_to_string(&grid)
+}
+
+fn bench_race(c: &mut Criterion) {
+    let (grid, race_info) = parse(&track_input(141));
+    c.bench_function("day20 part1", |b| {
+        b.iter(|| solve(&grid, &race_info, 100, 2))
+    });
+    c.bench_function("day20 part2", |b| {
+        b.iter(|| solve(&grid, &race_info, 100, 20))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_race
+}
+criterion_main!(benches);
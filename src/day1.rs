@@ -51,12 +51,12 @@ pub fn part2(input: &(Vec<usize>, Vec<usize>)) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "3   4\n\
-                                 4   3\n\
-                                 2   5\n\
-                                 1   3\n\
-                                 3   9\n\
-                                 3   3";
+    const INPUT: &str = "3   4\n\
+                         4   3\n\
+                         2   5\n\
+                         1   3\n\
+                         3   9\n\
+                         3   3";
 
     #[test]
     fn part1_example() {
@@ -53,12 +53,8 @@ fn explore_point(coord: &(i32, i32), grid: &[Vec<i32>], ends: &mut HashSet<(i32,
 #[aoc(day10, part1)]
 fn part1(input: &(Vec<Vec<i32>>, HashSet<(i32, i32)>)) -> usize {
     let mut count = 0;
-    let mut trail_iter = input.1.iter();
     let mut ends: HashSet<(i32, i32)> = HashSet::new();
-    loop {
-        let Some(curr_coord) = trail_iter.next() else {
-            break;
-        };
+    for curr_coord in input.1.iter() {
         let _ = explore_point(curr_coord, &input.0, &mut ends);
         count += ends.len();
         ends.clear();
@@ -70,11 +66,7 @@ fn part1(input: &(Vec<Vec<i32>>, HashSet<(i32, i32)>)) -> usize {
 fn part2(input: &(Vec<Vec<i32>>, HashSet<(i32, i32)>)) -> usize {
     let mut count = 0;
     let mut ends: HashSet<(i32, i32)> = HashSet::new();
-    let mut trail_iter = input.1.iter();
-    loop {
-        let Some(curr_coord) = trail_iter.next() else {
-            break;
-        };
+    for curr_coord in input.1.iter() {
         count += explore_point(curr_coord, &input.0, &mut ends);
     }
     count
@@ -84,14 +76,14 @@ fn part2(input: &(Vec<Vec<i32>>, HashSet<(i32, i32)>)) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "89010123\n\
-                                 78121874\n\
-                                 87430965\n\
-                                 96549874\n\
-                                 45678903\n\
-                                 32019012\n\
-                                 01329801\n\
-                                 10456732";
+    const INPUT: &str = "89010123\n\
+                         78121874\n\
+                         87430965\n\
+                         96549874\n\
+                         45678903\n\
+                         32019012\n\
+                         01329801\n\
+                         10456732";
 
     #[test]
     fn part1_example() {
@@ -14,7 +14,7 @@ fn update_number(n: u64) -> (u64, Option<u64>) {
         return (1, None);
     }
     let n_digits = n.ilog10() + 1;
-    if n_digits % 2 == 0 {
+    if n_digits.is_multiple_of(2) {
         let base = 10_u64.pow(n_digits / 2);
         let left = n / base;
         let right = n % base;
@@ -57,7 +57,7 @@ fn part2(input: &[u64]) -> u64 {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "125 17";
+    const INPUT: &str = "125 17";
 
     #[test]
     fn part1_example() {
@@ -4,10 +4,9 @@ use std::collections::HashSet;
 type Map = Vec<Vec<char>>;
 
 const DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-const DIAGS: [(i32, i32); 4] = [(-1, 1), (1, 1), (1, -1), (-1, -1)];
 
 #[aoc_generator(day12)]
-fn parse(input: &str) -> (Map, HashSet<char>) {
+pub fn parse(input: &str) -> (Map, HashSet<char>) {
     let grid_size = input.lines().count();
     let mut char_set: HashSet<char> = HashSet::new();
     let mut grid = vec![vec!['.'; grid_size + 2]; grid_size + 2];
@@ -20,114 +19,155 @@ fn parse(input: &str) -> (Map, HashSet<char>) {
     (grid, char_set)
 }
 
-fn explore_point(
-    curr_pos: &(i32, i32),
-    grid: &Map,
-    visited: &mut HashSet<(i32, i32)>,
-    mut tile_count: usize,
-    mut border_count: usize,
-    mut corner_count: usize,
-) -> (usize, usize, usize) {
-    let curr_char = grid[curr_pos.0 as usize][curr_pos.1 as usize];
-    // number of borders not in contact with same char
-    let mut borders = 4;
-    // new points of the same char to explore after that
-    let mut next_pos_explore: Vec<(i32, i32)> = Vec::new();
-
-    // find first state for is_prev_border
-    let prev_dir = DIRECTIONS[3];
-    let next_pos = ((curr_pos.0 + prev_dir.0), (curr_pos.1 + prev_dir.1));
-    let next_char = grid[next_pos.0 as usize][next_pos.1 as usize];
-    let mut is_prev_border = next_char != curr_char;
-
-    for (dir, diag) in DIRECTIONS.iter().zip(DIAGS) {
-        let next_pos = ((curr_pos.0 + dir.0), (curr_pos.1 + dir.1));
-        let next_char = grid[next_pos.0 as usize][next_pos.1 as usize];
-        if next_char == curr_char {
-            borders -= 1;
-            if visited.get(&next_pos).is_none() {
-                visited.insert(next_pos);
-                next_pos_explore.push(next_pos);
-                tile_count += 1;
-            }
-            if !is_prev_border {
-                // need to check what's the char in the diagonal to the current position
-                // to check for "inside" corners
-                let diag_pos = ((curr_pos.0 + diag.0), (curr_pos.1 + diag.1));
-                let diag_char = grid[diag_pos.0 as usize][diag_pos.1 as usize];
-                if diag_char != curr_char {
-                    corner_count += 1;
-                }
-            }
-            is_prev_border = false;
-        } else {
-            if is_prev_border {
-                // "outside" corner
-                corner_count += 1;
+/// A region of plants of the same type. Cells are (row, column) positions
+/// in the input, sorted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub plant: char,
+    pub cells: Vec<(usize, usize)>,
+    pub perimeter: usize,
+    pub sides: usize,
+}
+
+impl Region {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Top left and bottom right cells of the smallest rectangle holding the region
+    pub fn bounding_box(&self) -> ((usize, usize), (usize, usize)) {
+        let rows = self.cells.iter().map(|(i, _)| *i);
+        let cols = self.cells.iter().map(|(_, j)| *j);
+        (
+            (rows.clone().min().unwrap(), cols.clone().min().unwrap()),
+            (rows.max().unwrap(), cols.max().unwrap()),
+        )
+    }
+
+    /// Price of the fence, using the perimeter
+    pub fn price(&self) -> usize {
+        self.area() * self.perimeter
+    }
+
+    /// Price of the fence with the bulk discount, using the number of sides
+    pub fn bulk_price(&self) -> usize {
+        self.area() * self.sides
+    }
+}
+
+/// All the regions of the garden, in the order their top left cell is found
+pub fn regions(grid: &Map) -> Vec<Region> {
+    let mut visited = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut regions = Vec::new();
+    for i in 1..(grid.len() - 1) {
+        for j in 1..(grid[i].len() - 1) {
+            if !visited[i][j] {
+                visited[i][j] = true;
+                let mut region = Region {
+                    plant: grid[i][j],
+                    cells: Vec::new(),
+                    perimeter: 0,
+                    sides: 0,
+                };
+                explore_region(&(i as i32, j as i32), grid, &mut visited, &mut region);
+                region.cells.sort();
+                regions.push(region);
             }
-            is_prev_border = true;
         }
     }
-    border_count += borders;
-    for pos in next_pos_explore.iter() {
-        (tile_count, border_count, corner_count) =
-            explore_point(pos, grid, visited, tile_count, border_count, corner_count);
+    regions
+}
+
+/// One unit of fence, on the side `DIRECTIONS[dir]` of a cell. `line` is the
+/// row of horizontal fences and the column of vertical ones, `along` the
+/// position on that line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct FenceEdge {
+    dir: usize,
+    line: i32,
+    along: i32,
+}
+
+impl FenceEdge {
+    fn new(pos: &(i32, i32), dir: usize) -> Self {
+        let (line, along) = if DIRECTIONS[dir].0 == 0 {
+            // left or right of the cell: vertical fence
+            (pos.1, pos.0)
+        } else {
+            (pos.0, pos.1)
+        };
+        FenceEdge { dir, line, along }
     }
-    (tile_count, border_count, corner_count)
 }
 
-#[aoc(day12, part1)]
-fn part1((grid, _char_set): &(Map, HashSet<char>)) -> usize {
-    let grid_size = grid.len();
-    let mut visited: HashSet<(i32, i32)> = HashSet::new();
-    let mut total = 0;
-    for i in 1..(grid_size - 1) {
-        for j in 1..(grid_size - 1) {
-            let curr_pos = (i as i32, j as i32);
-            if !visited.contains(&curr_pos) {
-                visited.insert(curr_pos);
-                let (tile_count, border_count, _corner_count) =
-                    explore_point(&curr_pos, grid, &mut visited, 1, 0, 0);
-                total += tile_count * border_count;
+/// Number of sides made by the fence edges: edges facing the same way
+/// that follow each other on a line are merged into a single side.
+fn count_sides(edges: &mut [FenceEdge]) -> usize {
+    edges.sort();
+    let breaks = edges
+        .windows(2)
+        .filter(|w| (w[0].dir, w[0].line, w[0].along + 1) != (w[1].dir, w[1].line, w[1].along))
+        .count();
+    if edges.is_empty() {
+        0
+    } else {
+        breaks + 1
+    }
+}
+
+/// Explore the region holding `start` with an explicit stack, so that
+/// large regions do not overflow the call stack.
+fn explore_region(start: &(i32, i32), grid: &Map, visited: &mut [Vec<bool>], region: &mut Region) {
+    let curr_char = grid[start.0 as usize][start.1 as usize];
+    let mut edges: Vec<FenceEdge> = Vec::new();
+    // points of the same char still to explore
+    let mut stack: Vec<(i32, i32)> = vec![*start];
+    while let Some(curr_pos) = stack.pop() {
+        // grid has a border of '.' around the input
+        region
+            .cells
+            .push((curr_pos.0 as usize - 1, curr_pos.1 as usize - 1));
+        for (dir, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+            let next_pos = (curr_pos.0 + dx, curr_pos.1 + dy);
+            if grid[next_pos.0 as usize][next_pos.1 as usize] == curr_char {
+                let seen = &mut visited[next_pos.0 as usize][next_pos.1 as usize];
+                if !*seen {
+                    *seen = true;
+                    stack.push(next_pos);
+                }
+            } else {
+                edges.push(FenceEdge::new(&curr_pos, dir));
             }
         }
     }
-    total
+    region.perimeter = edges.len();
+    region.sides = count_sides(&mut edges);
+}
+
+#[aoc(day12, part1)]
+fn part1((grid, _char_set): &(Map, HashSet<char>)) -> usize {
+    regions(grid).iter().map(Region::price).sum()
 }
 
 #[aoc(day12, part2)]
 fn part2((grid, _char_set): &(Map, HashSet<char>)) -> usize {
-    let grid_size = grid.len();
-    let mut visited: HashSet<(i32, i32)> = HashSet::new();
-    let mut total = 0;
-    for i in 1..(grid_size - 1) {
-        for j in 1..(grid_size - 1) {
-            let curr_pos = (i as i32, j as i32);
-            if !visited.contains(&curr_pos) {
-                visited.insert(curr_pos);
-                let (tile_count, _border_count, corner_count) =
-                    explore_point(&curr_pos, grid, &mut visited, 1, 0, 0);
-                total += tile_count * corner_count;
-            }
-        }
-    }
-    total
+    regions(grid).iter().map(Region::bulk_price).sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "RRRRIICCFF\n\
-                                 RRRRIICCCF\n\
-                                 VVRRRCCFFF\n\
-                                 VVRCCCJFFF\n\
-                                 VVVVCJJCFE\n\
-                                 VVIVCCJJEE\n\
-                                 VVIIICJJEE\n\
-                                 MIIIIIJJEE\n\
-                                 MIIISIJEEE\n\
-                                 MMMISSJEEE";
+    const INPUT: &str = "RRRRIICCFF\n\
+                         RRRRIICCCF\n\
+                         VVRRRCCFFF\n\
+                         VVRCCCJFFF\n\
+                         VVVVCJJCFE\n\
+                         VVIVCCJJEE\n\
+                         VVIIICJJEE\n\
+                         MIIIIIJJEE\n\
+                         MIIISIJEEE\n\
+                         MMMISSJEEE";
 
     #[test]
     fn part1_example() {
@@ -138,4 +178,92 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(INPUT)), 1206);
     }
+
+    #[test]
+    fn nested_regions() {
+        let input = "OOOOO\n\
+                     OXOXO\n\
+                     OOOOO\n\
+                     OXOXO\n\
+                     OOOOO";
+        let (grid, _) = parse(input);
+        let regions = regions(&grid);
+        assert_eq!(regions.len(), 5);
+        let outer = &regions[0];
+        assert_eq!(outer.plant, 'O');
+        assert_eq!(outer.area(), 21);
+        assert_eq!(outer.perimeter, 36);
+        assert_eq!(outer.sides, 20);
+        assert_eq!(outer.bounding_box(), ((0, 0), (4, 4)));
+        let inner = &regions[1];
+        assert_eq!(inner.plant, 'X');
+        assert_eq!(inner.cells, vec![(1, 1)]);
+        assert_eq!(inner.perimeter, 4);
+        assert_eq!(inner.sides, 4);
+        assert_eq!(inner.bounding_box(), ((1, 1), (1, 1)));
+        assert_eq!(regions.iter().map(Region::price).sum::<usize>(), 772);
+        assert_eq!(regions.iter().map(Region::bulk_price).sum::<usize>(), 436);
+    }
+
+    #[test]
+    fn large_region() {
+        let size = 2000;
+        let input = vec!["A".repeat(size); size].join("\n");
+        let (grid, _) = parse(&input);
+        let regions = regions(&grid);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].area(), size * size);
+        assert_eq!(regions[0].perimeter, 4 * size);
+        assert_eq!(regions[0].sides, 4);
+    }
+
+    #[test]
+    fn part2_diagonal_regions() {
+        // the two B regions only touch the big A region, and each other, by a corner
+        let input = "AAAAAA\n\
+                     AAABBA\n\
+                     AAABBA\n\
+                     ABBAAA\n\
+                     ABBAAA\n\
+                     AAAAAA";
+        let (grid, _) = parse(input);
+        let regions = regions(&grid);
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions[0].area(), 28);
+        assert_eq!(regions[0].sides, 12);
+        assert_eq!(part2(&parse(input)), 368);
+    }
+
+    #[test]
+    fn part2_e_shape() {
+        let input = "EEEEE\n\
+                     EXXXX\n\
+                     EEEEE\n\
+                     EXXXX\n\
+                     EEEEE";
+        let (grid, _) = parse(input);
+        let regions = regions(&grid);
+        assert_eq!(regions[0].area(), 17);
+        assert_eq!(regions[0].sides, 12);
+        assert_eq!(part2(&parse(input)), 236);
+    }
+
+    #[test]
+    fn merge_fence_edges() {
+        let edge = |dir, line, along| FenceEdge { dir, line, along };
+        assert_eq!(count_sides(&mut []), 0);
+        // a row of three cells seen from above, given in any order
+        assert_eq!(
+            count_sides(&mut [edge(3, 1, 3), edge(3, 1, 1), edge(3, 1, 2)]),
+            1
+        );
+        // a gap, another line or another direction make new sides
+        assert_eq!(count_sides(&mut [edge(3, 1, 1), edge(3, 1, 3)]), 2);
+        assert_eq!(count_sides(&mut [edge(3, 1, 1), edge(3, 2, 2)]), 2);
+        assert_eq!(count_sides(&mut [edge(3, 1, 1), edge(1, 1, 2)]), 2);
+        // vertical fences lie on the column of the cell, horizontal ones on its row
+        let cell = (2, 5);
+        assert_eq!(FenceEdge::new(&cell, 0), edge(0, 5, 2));
+        assert_eq!(FenceEdge::new(&cell, 1), edge(1, 2, 5));
+    }
 }
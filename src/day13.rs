@@ -92,21 +92,21 @@ fn part2((matrices, prizes): &(Matrices, Prizes)) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    const INPUT: &'static str = "Button A: X+94, Y+34\n\
-                                 Button B: X+22, Y+67\n\
-                                 Prize: X=8400, Y=5400\n\
-                                 \n\
-                                 Button A: X+26, Y+66\n\
-                                 Button B: X+67, Y+21\n\
-                                 Prize: X=12748, Y=12176\n\
-                                 \n\
-                                 Button A: X+17, Y+86\n\
-                                 Button B: X+84, Y+37\n\
-                                 Prize: X=7870, Y=6450\n\
-                                 \n\
-                                 Button A: X+69, Y+23\n\
-                                 Button B: X+27, Y+71\n\
-                                 Prize: X=18641, Y=10279";
+    const INPUT: &str = "Button A: X+94, Y+34\n\
+                         Button B: X+22, Y+67\n\
+                         Prize: X=8400, Y=5400\n\
+                         \n\
+                         Button A: X+26, Y+66\n\
+                         Button B: X+67, Y+21\n\
+                         Prize: X=12748, Y=12176\n\
+                         \n\
+                         Button A: X+17, Y+86\n\
+                         Button B: X+84, Y+37\n\
+                         Prize: X=7870, Y=6450\n\
+                         \n\
+                         Button A: X+69, Y+23\n\
+                         Button B: X+27, Y+71\n\
+                         Prize: X=18641, Y=10279";
 
     #[test]
     fn part1_example() {
@@ -120,18 +120,18 @@ fn part2((pos, veloc): &(Xy, Xy)) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "p=0,4 v=3,-3\n\
-                                 p=6,3 v=-1,-3\n\
-                                 p=10,3 v=-1,2\n\
-                                 p=2,0 v=2,-1\n\
-                                 p=0,0 v=1,3\n\
-                                 p=3,0 v=-2,-2\n\
-                                 p=7,6 v=-1,-3\n\
-                                 p=3,0 v=-1,-2\n\
-                                 p=9,3 v=2,3\n\
-                                 p=7,3 v=-1,2\n\
-                                 p=2,4 v=2,-3\n\
-                                 p=9,5 v=-3,-3";
+    const INPUT: &str = "p=0,4 v=3,-3\n\
+                         p=6,3 v=-1,-3\n\
+                         p=10,3 v=-1,2\n\
+                         p=2,0 v=2,-1\n\
+                         p=0,0 v=1,3\n\
+                         p=3,0 v=-2,-2\n\
+                         p=7,6 v=-1,-3\n\
+                         p=3,0 v=-1,-2\n\
+                         p=9,3 v=2,3\n\
+                         p=7,3 v=-1,2\n\
+                         p=2,4 v=2,-3\n\
+                         p=9,5 v=-3,-3";
 
     #[test]
     fn part1_example() {
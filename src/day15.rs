@@ -1,10 +1,11 @@
+use crate::grid::grid_to_string;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashMap;
+use std::ops::Range;
 
 type Grid = Vec<Vec<char>>;
 type Move = (isize, isize);
 
-fn parse_moves(moves_input: &str) -> Vec<Move> {
+pub fn parse_moves(moves_input: &str) -> Vec<Move> {
     let n_rows = moves_input.lines().count();
     let n_cols = moves_input.lines().next().unwrap().chars().count();
     let mut moves: Vec<Move> = Vec::with_capacity(n_rows * n_cols);
@@ -23,220 +24,589 @@ fn parse_moves(moves_input: &str) -> Vec<Move> {
     moves
 }
 
-#[aoc_generator(day15, part1)]
-fn parse1(input: &str) -> (Grid, Move, Vec<Move>) {
-    let (grid_input, moves_input) = input.split_once("\n\n").unwrap();
+/// A box covering `width` tiles, starting from `position` and going right.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct WarehouseBox {
+    pub position: Move,
+    pub width: usize,
+}
+
+impl WarehouseBox {
+    fn tiles(&self) -> impl Iterator<Item = Move> + '_ {
+        (0..self.width as isize).map(|k| (self.position.0, self.position.1 + k))
+    }
+}
+
+/// A move done by the robot, with the range of `Warehouse::history_boxes`
+/// holding the boxes it pushed (`None` if it was blocked).
+#[derive(Clone, Debug)]
+struct HistoryEntry {
+    direction: Move,
+    pushed: Option<Range<usize>>,
+}
+
+/// Warehouse with walls, boxes of arbitrary width and a single robot.
+#[derive(Clone, Debug)]
+pub struct Warehouse {
+    n_cols: usize,
+    walls: Vec<bool>,
+    // index in `boxes` of the box covering each tile, if any
+    occupancy: Vec<Option<usize>>,
+    boxes: Vec<WarehouseBox>,
+    robot: Move,
+    // whether moves are recorded so they can be undone
+    record_history: bool,
+    history: Vec<HistoryEntry>,
+    // pushed boxes of all moves in `history`, one after the other
+    history_boxes: Vec<usize>,
+    undone: Vec<Move>,
+    // buffers reused by every move, to avoid allocations
+    frontier: Vec<Move>,
+    box_to_move: Vec<usize>,
+    // last move at which each box was added to `box_to_move`
+    seen_at: Vec<usize>,
+    move_count: usize,
+}
 
-    let n_rows = grid_input.lines().count();
-    let n_cols = grid_input.lines().next().unwrap().chars().count();
-    let mut grid: Grid = vec![vec!['.'; n_cols]; n_rows];
-    let mut curr_pos = (0, 0);
-    for (i, l) in grid_input.lines().enumerate() {
-        for (j, c) in l.chars().enumerate() {
-            if (c == '#') || (c == 'O') || (c == '@') {
-                grid[i][j] = c;
+impl Warehouse {
+    /// Build the warehouse from the puzzle map, every tile being `box_width` times wider.
+    pub fn new(grid_input: &str, box_width: usize) -> Self {
+        let n_rows = grid_input.lines().count();
+        let n_cols = grid_input.lines().next().unwrap().chars().count() * box_width;
+        let mut walls = vec![false; n_rows * n_cols];
+        let mut boxes: Vec<WarehouseBox> = Vec::new();
+        let mut robot = (0, 0);
+        for (i, l) in grid_input.lines().enumerate() {
+            for (j, c) in l.chars().enumerate() {
+                let pos = (i as isize, (j * box_width) as isize);
+                match c {
+                    '#' => {
+                        let start = i * n_cols + j * box_width;
+                        walls[start..start + box_width].fill(true)
+                    }
+                    'O' => boxes.push(WarehouseBox {
+                        position: pos,
+                        width: box_width,
+                    }),
+                    '@' => robot = pos,
+                    _ => (),
+                }
             }
-            if c == '@' {
-                curr_pos = (i as isize, j as isize);
+        }
+        let mut occupancy = vec![None; n_rows * n_cols];
+        for (idx, b) in boxes.iter().enumerate() {
+            for tile in b.tiles() {
+                occupancy[tile.0 as usize * n_cols + tile.1 as usize] = Some(idx);
             }
         }
+        let n_boxes = boxes.len();
+        Warehouse {
+            n_cols,
+            walls,
+            occupancy,
+            boxes,
+            robot,
+            record_history: false,
+            history: Vec::new(),
+            history_boxes: Vec::new(),
+            undone: Vec::new(),
+            frontier: Vec::new(),
+            box_to_move: Vec::new(),
+            seen_at: vec![0; n_boxes],
+            move_count: 0,
+        }
     }
-    let moves = parse_moves(moves_input);
 
-    (grid, curr_pos, moves)
-}
+    /// Record every move from now on, so that it can be undone.
+    pub fn with_history(mut self) -> Self {
+        self.record_history = true;
+        self
+    }
 
-#[aoc_generator(day15, part2)]
-fn parse2(input: &str) -> (Grid, Move, Vec<Move>) {
-    let (grid_input, moves_input) = input.split_once("\n\n").unwrap();
-    let grid_input = grid_input
-        .replace(".", "..")
-        .replace("O", "[]")
-        .replace("#", "##")
-        .replace("@", "@.");
-    let n_rows = grid_input.lines().count();
-    let n_cols = grid_input.lines().next().unwrap().chars().count();
-    let mut grid: Grid = vec![vec!['.'; n_cols]; n_rows];
-    let mut curr_pos = (0, 0);
-    for (i, l) in grid_input.lines().enumerate() {
-        for (j, c) in l.chars().enumerate() {
-            grid[i][j] = c;
-            if c == '@' {
-                curr_pos = (i as isize, j as isize);
+    pub fn robot(&self) -> Move {
+        self.robot
+    }
+
+    pub fn boxes(&self) -> &[WarehouseBox] {
+        &self.boxes
+    }
+
+    fn index(&self, pos: &Move) -> usize {
+        pos.0 as usize * self.n_cols + pos.1 as usize
+    }
+
+    fn is_wall(&self, pos: &Move) -> bool {
+        self.walls[self.index(pos)]
+    }
+
+    fn box_at(&self, pos: &Move) -> Option<usize> {
+        self.occupancy[self.index(pos)]
+    }
+
+    /// Fill `box_to_move` with the boxes pushed, directly or not, when the robot
+    /// moves in `direction`. Returns false if one of them (or the robot) would hit a wall.
+    fn boxes_to_push(&mut self, direction: &Move) -> bool {
+        self.move_count += 1;
+        self.box_to_move.clear();
+        // tiles that need to be free for the move to happen
+        self.frontier.clear();
+        self.frontier
+            .push((self.robot.0 + direction.0, self.robot.1 + direction.1));
+        while let Some(pos) = self.frontier.pop() {
+            if self.is_wall(&pos) {
+                return false;
+            }
+            let Some(idx) = self.box_at(&pos) else {
+                continue;
+            };
+            if self.seen_at[idx] == self.move_count {
+                continue;
+            }
+            self.seen_at[idx] = self.move_count;
+            self.box_to_move.push(idx);
+            for tile in self.boxes[idx].tiles() {
+                let next_pos = (tile.0 + direction.0, tile.1 + direction.1);
+                if self.box_at(&next_pos) != Some(idx) {
+                    self.frontier.push(next_pos);
+                }
             }
         }
+        true
+    }
+
+    /// Move the robot one tile, pushing boxes in front of it.
+    /// Returns false if the move is blocked.
+    pub fn step(&mut self, direction: &Move) -> bool {
+        if !self.record_history {
+            return self.move_robot(direction);
+        }
+        self.undone.clear();
+        self.record_step(direction)
+    }
+
+    fn record_step(&mut self, direction: &Move) -> bool {
+        let moved = self.move_robot(direction);
+        let pushed = moved.then(|| {
+            let start = self.history_boxes.len();
+            self.history_boxes.extend_from_slice(&self.box_to_move);
+            start..self.history_boxes.len()
+        });
+        self.history.push(HistoryEntry {
+            direction: *direction,
+            pushed,
+        });
+        moved
+    }
+
+    /// Cancel the last move. Returns false if there is nothing to undo,
+    /// which is always the case without `with_history`.
+    pub fn undo(&mut self) -> bool {
+        let Some(entry) = self.history.pop() else {
+            return false;
+        };
+        if let Some(pushed) = entry.pushed {
+            let back = (-entry.direction.0, -entry.direction.1);
+            let history_boxes = std::mem::take(&mut self.history_boxes);
+            self.shift(&history_boxes[pushed.clone()], &back);
+            self.history_boxes = history_boxes;
+            self.history_boxes.truncate(pushed.start);
+            self.robot = (self.robot.0 + back.0, self.robot.1 + back.1);
+        }
+        self.undone.push(entry.direction);
+        true
+    }
+
+    /// Play again the last undone move. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(direction) = self.undone.pop() else {
+            return false;
+        };
+        self.record_step(&direction);
+        true
     }
-    let moves = parse_moves(moves_input);
-    (grid, curr_pos, moves)
-}
 
-fn make_move(
-    curr_pos: &Move,
-    next_move: &Move,
-    grid: &mut Grid,
-    mut robot_pos: Move,
-    move_ok: &mut bool,
-) -> Move {
-    let curr_item = grid[curr_pos.0 as usize][curr_pos.1 as usize];
-    let next_pos = (curr_pos.0 + next_move.0, curr_pos.1 + next_move.1);
-    let next_item = grid[next_pos.0 as usize][next_pos.1 as usize];
-    if next_item == '#' {
-        *move_ok = false;
-    } else if next_item == 'O' {
-        let _ = make_move(&next_pos, next_move, grid, robot_pos, move_ok);
-    }
-    if *move_ok {
-        grid[next_pos.0 as usize][next_pos.1 as usize] = curr_item;
-        if curr_item == '@' {
-            robot_pos = (next_pos.0, next_pos.1);
-            grid[curr_pos.0 as usize][curr_pos.1 as usize] = '.';
+    /// Number of moves recorded so far, blocked ones included
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Same as `step` without history.
+    /// The boxes that were pushed are left in `box_to_move`.
+    fn move_robot(&mut self, direction: &Move) -> bool {
+        if !self.boxes_to_push(direction) {
+            return false;
+        }
+        let box_to_move = std::mem::take(&mut self.box_to_move);
+        self.shift(&box_to_move, direction);
+        self.box_to_move = box_to_move;
+        self.robot = (self.robot.0 + direction.0, self.robot.1 + direction.1);
+        true
+    }
+
+    fn shift(&mut self, box_to_move: &[usize], direction: &Move) {
+        // reset all positions
+        for idx in box_to_move.iter() {
+            for tile in self.boxes[*idx].tiles() {
+                let i = self.index(&tile);
+                self.occupancy[i] = None;
+            }
+        }
+        // update new positions
+        for idx in box_to_move.iter() {
+            let b = &mut self.boxes[*idx];
+            b.position = (b.position.0 + direction.0, b.position.1 + direction.1);
+            let b = *b;
+            for tile in b.tiles() {
+                let i = self.index(&tile);
+                self.occupancy[i] = Some(*idx);
+            }
+        }
+    }
+
+    /// Sum of the GPS coordinates of all boxes, measured from their left edge.
+    pub fn gps(&self) -> usize {
+        self.boxes
+            .iter()
+            .map(|b| 100 * b.position.0 as usize + b.position.1 as usize)
+            .sum()
+    }
+
+    /// Content of a tile, boxes being drawn `O` when 1 tile wide or `[-]` otherwise.
+    pub fn tile(&self, pos: &Move) -> char {
+        if pos == &self.robot {
+            return '@';
+        }
+        if self.is_wall(pos) {
+            return '#';
+        }
+        let Some(idx) = self.box_at(pos) else {
+            return '.';
+        };
+        let b = &self.boxes[idx];
+        let k = (pos.1 - b.position.1) as usize;
+        match k {
+            _ if b.width == 1 => 'O',
+            0 => '[',
+            _ if k == b.width - 1 => ']',
+            _ => '-',
         }
     }
-    robot_pos
+
+    pub fn to_grid(&self) -> Grid {
+        let n_rows = self.walls.len() / self.n_cols;
+        (0..n_rows as isize)
+            .map(|i| {
+                (0..self.n_cols as isize)
+                    .map(|j| self.tile(&(i, j)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Warehouse {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", grid_to_string(&self.to_grid()))
+    }
 }
 
-fn move_robot(curr_pos: &mut Move, next_move: &Move, grid: &mut Grid, move_ok: &mut bool) {
-    let mut box_to_move: HashMap<Move, char> = HashMap::new();
-    let next_pos = (curr_pos.0 + next_move.0, curr_pos.1 + next_move.1);
-    let next_item = grid[next_pos.0 as usize][next_pos.1 as usize];
-    if next_item == '#' {
-        *move_ok = false;
-    } else if (next_item == ']') || (next_item == '[') {
-        box_to_move.insert(next_pos, next_item);
-        explore_box(&next_pos, next_move, grid, move_ok, &mut box_to_move);
-    }
-    if *move_ok {
-        move_boxes(&box_to_move, next_move, grid);
-        grid[next_pos.0 as usize][next_pos.1 as usize] = '@';
-        grid[curr_pos.0 as usize][curr_pos.1 as usize] = '.';
-        *curr_pos = next_pos;
+fn move_char(mv: &Move) -> char {
+    match mv {
+        (0, -1) => '<',
+        (1, 0) => 'v',
+        (-1, 0) => '^',
+        (0, 1) => '>',
+        _ => '?',
     }
 }
 
-fn move_boxes(boxes: &HashMap<Move, char>, direction: &Move, grid: &mut Grid) {
-    // reset all positions
-    boxes.iter().for_each(|(pos, c)| {
-        grid[pos.0 as usize][pos.1 as usize] = '.';
-        if c == &'[' {
-            grid[pos.0 as usize][(pos.1 + 1) as usize] = '.';
-        } else {
-            grid[pos.0 as usize][(pos.1 - 1) as usize] = '.';
+/// Tiles changed by a move, with their new content.
+pub type Delta = Vec<(Move, char)>;
+
+/// Step by step record of a simulation.
+pub struct Replay {
+    initial: Grid,
+    moves: Vec<Move>,
+    deltas: Vec<Delta>,
+}
+
+impl Replay {
+    pub fn record(warehouse: &Warehouse, moves: &[Move]) -> Self {
+        let mut warehouse = warehouse.clone();
+        let initial = warehouse.to_grid();
+        let mut current = initial.clone();
+        let mut deltas: Vec<Delta> = Vec::with_capacity(moves.len());
+        for mv in moves.iter() {
+            // only the robot and the pushed boxes can change
+            let mut touched: Vec<Move> = vec![warehouse.robot()];
+            if warehouse.move_robot(mv) {
+                for idx in warehouse.box_to_move.iter() {
+                    for tile in warehouse.boxes[*idx].tiles() {
+                        touched.push(tile);
+                        touched.push((tile.0 - mv.0, tile.1 - mv.1));
+                    }
+                }
+                touched.push(warehouse.robot());
+            }
+            let mut delta: Delta = Vec::new();
+            for pos in touched {
+                let c = warehouse.tile(&pos);
+                if current[pos.0 as usize][pos.1 as usize] != c {
+                    current[pos.0 as usize][pos.1 as usize] = c;
+                    delta.push((pos, c));
+                }
+            }
+            deltas.push(delta);
         }
-    });
-    // update new positions
-    boxes.iter().for_each(|(pos, c)| {
-        grid[(pos.0 + direction.0) as usize][(pos.1 + direction.1) as usize] = *c;
-        if c == &'[' {
-            grid[(pos.0 + direction.0) as usize][(pos.1 + 1 + direction.1) as usize] = ']';
-        } else {
-            grid[(pos.0 + direction.0) as usize][(pos.1 - 1 + direction.1) as usize] = '[';
+        Replay {
+            initial,
+            moves: moves.to_vec(),
+            deltas,
         }
-    });
-}
+    }
+
+    /// Number of recorded moves
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Tiles changed by the `n`-th move (starting at 1)
+    pub fn delta(&self, n: usize) -> &Delta {
+        &self.deltas[n - 1]
+    }
+
+    /// Warehouse after the first `n` moves
+    pub fn frame(&self, n: usize) -> Grid {
+        let mut grid = self.initial.clone();
+        for delta in self.deltas[..n].iter() {
+            for (pos, c) in delta.iter() {
+                grid[pos.0 as usize][pos.1 as usize] = *c;
+            }
+        }
+        grid
+    }
 
-fn explore_box(
-    pos: &Move,
-    next_move: &Move,
-    grid: &Grid,
-    move_ok: &mut bool,
-    box_to_move: &mut HashMap<Move, char>,
-) {
-    if next_move.0 == 0 {
-        // Horizontal direction, only one box can be moved.
-        // But they have a width of 2 so need to account for that.
-        let next_pos = (pos.0, pos.1 + next_move.1 * 2);
-        let next_item = grid[next_pos.0 as usize][next_pos.1 as usize];
-        if next_item == '#' {
-            *move_ok = false;
-        } else if (next_item == ']') || (next_item == '[') {
-            box_to_move.insert(next_pos, next_item);
-            explore_box(&next_pos, next_move, grid, move_ok, box_to_move);
+    /// Every frame, each one preceded by a `Move i/n: <dir>` header line.
+    pub fn animation(&self) -> String {
+        let mut grid = self.initial.clone();
+        let mut out = format!("Initial state:\n{}\n", grid_to_string(&grid));
+        for (i, (mv, delta)) in self.moves.iter().zip(self.deltas.iter()).enumerate() {
+            for (pos, c) in delta.iter() {
+                grid[pos.0 as usize][pos.1 as usize] = *c;
+            }
+            out.push_str(&format!(
+                "\nMove {}/{}: {}\n{}\n",
+                i + 1,
+                self.len(),
+                move_char(mv),
+                grid_to_string(&grid)
+            ));
         }
-    } else {
-        // Vertical move. Can move multiple boxes.
-        let curr_item = grid[pos.0 as usize][pos.1 as usize];
-        // adjust current pos to '['
-        let pos = (pos.0, if curr_item == '[' { pos.1 } else { pos.1 - 1 });
-        let mut to_explore: Vec<Move> = Vec::new();
-        // check what's next
-        for i in [-1, 0, 1].iter() {
-            let next_pos = (pos.0 + next_move.0, pos.1 + i);
-            let next_item = grid[next_pos.0 as usize][next_pos.1 as usize];
-            if (i != &-1) && (next_item == '#') {
-                *move_ok = false;
-            } else if next_item == '[' {
-                box_to_move.insert(next_pos, next_item);
-                to_explore.push(next_pos);
+        out
+    }
+
+    /// One line per move: its index, direction and changed tiles as `row,col=c`.
+    pub fn delta_log(&self) -> String {
+        self.moves
+            .iter()
+            .zip(self.deltas.iter())
+            .enumerate()
+            .map(|(i, (mv, delta))| {
+                let mut line = format!("{} {}", i + 1, move_char(mv));
+                for (pos, c) in delta.iter() {
+                    line.push_str(&format!(" {},{}={}", pos.0, pos.1, c));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Broken invariant of the warehouse, with the tile where it was detected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// `[` without its `]`, or the other way around
+    OrphanBoxEdge(Move),
+    /// Wall added or removed
+    WallChanged(Move),
+    /// Two boxes on the same tile
+    BoxOverlap(Move),
+    /// Box or robot on a wall
+    InWall(Move),
+    BoxCount {
+        expected: usize,
+        found: usize,
+    },
+    RobotCount(usize),
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Violation::OrphanBoxEdge(pos) => write!(f, "orphan box edge at {:?}", pos),
+            Violation::WallChanged(pos) => write!(f, "wall changed at {:?}", pos),
+            Violation::BoxOverlap(pos) => write!(f, "boxes overlapping at {:?}", pos),
+            Violation::InWall(pos) => write!(f, "object inside a wall at {:?}", pos),
+            Violation::BoxCount { expected, found } => {
+                write!(f, "expected {} boxes, found {}", expected, found)
             }
+            Violation::RobotCount(n) => write!(f, "expected 1 robot, found {}", n),
         }
-        if !to_explore.is_empty() {
-            for nxt_pos in to_explore.iter() {
-                explore_box(nxt_pos, next_move, grid, move_ok, box_to_move);
+    }
+}
+
+fn count_boxes(grid: &Grid) -> usize {
+    grid.iter()
+        .flatten()
+        .filter(|c| (**c == 'O') || (**c == '['))
+        .count()
+}
+
+/// Check a drawn warehouse against its initial state: walls untouched,
+/// same number of boxes, a single robot, and every `[` closed by a `]`.
+pub fn check_grid(grid: &Grid, initial: &Grid) -> Result<(), Violation> {
+    let mut robots = 0;
+    for (i, row) in grid.iter().enumerate() {
+        // column of the `[` of the box being read, if any
+        let mut open: Option<usize> = None;
+        for (j, c) in row.iter().enumerate() {
+            let pos = (i as isize, j as isize);
+            if (*c == '#') != (initial[i][j] == '#') {
+                return Err(Violation::WallChanged(pos));
             }
+            match (c, open) {
+                ('[', Some(k)) => return Err(Violation::OrphanBoxEdge((i as isize, k as isize))),
+                ('[', None) => open = Some(j),
+                ('-', None) | (']', None) => return Err(Violation::OrphanBoxEdge(pos)),
+                ('-', Some(_)) => (),
+                (']', Some(_)) => open = None,
+                (_, Some(k)) => return Err(Violation::OrphanBoxEdge((i as isize, k as isize))),
+                _ => (),
+            }
+            if *c == '@' {
+                robots += 1;
+            }
+        }
+        if let Some(k) = open {
+            return Err(Violation::OrphanBoxEdge((i as isize, k as isize)));
         }
     }
+    let (expected, found) = (count_boxes(initial), count_boxes(grid));
+    if expected != found {
+        return Err(Violation::BoxCount { expected, found });
+    }
+    if robots != 1 {
+        return Err(Violation::RobotCount(robots));
+    }
+    Ok(())
 }
 
-fn compute_gps(grid: &Grid, c: char) -> usize {
-    let mut total = 0;
-    let n_row = grid.len();
-    let n_col = grid[0].len();
-    for (i, row) in grid.iter().enumerate().take(n_row) {
-        for (j, cc) in row.iter().enumerate().take(n_col) {
-            if cc == &c {
-                total += 100 * i + j;
+impl Warehouse {
+    /// Check that boxes neither overlap nor sit in a wall, then check the
+    /// drawn warehouse with `check_grid`.
+    pub fn check(&self, initial: &Grid) -> Result<(), Violation> {
+        for (idx, b) in self.boxes.iter().enumerate() {
+            for tile in b.tiles() {
+                if self.is_wall(&tile) {
+                    return Err(Violation::InWall(tile));
+                }
+                if self.box_at(&tile) != Some(idx) {
+                    return Err(Violation::BoxOverlap(tile));
+                }
             }
         }
+        if self.is_wall(&self.robot) {
+            return Err(Violation::InWall(self.robot));
+        }
+        if self.box_at(&self.robot).is_some() {
+            return Err(Violation::BoxOverlap(self.robot));
+        }
+        check_grid(&self.to_grid(), initial)
     }
-    total
 }
 
-#[aoc(day15, part1)]
-fn part1((grid, curr_pos, moves): &(Grid, Move, Vec<Move>)) -> usize {
-    let mut grid = grid.clone();
-    let mut robot_pos = *curr_pos;
-    for mv in moves.iter() {
-        robot_pos = make_move(&robot_pos, mv, &mut grid, robot_pos, &mut true);
+/// Run the moves, checking invariants after each of them.
+/// On failure, returns the index of the first faulty move (starting at 1).
+pub fn simulate_checked(
+    warehouse: &Warehouse,
+    moves: &[Move],
+) -> Result<Warehouse, (usize, Violation)> {
+    let mut warehouse = warehouse.clone();
+    let initial = warehouse.to_grid();
+    warehouse.check(&initial).map_err(|v| (0, v))?;
+    for (i, mv) in moves.iter().enumerate() {
+        warehouse.step(mv);
+        warehouse.check(&initial).map_err(|v| (i + 1, v))?;
     }
-    compute_gps(&grid, 'O')
+    Ok(warehouse)
 }
 
-#[aoc(day15, part2)]
-fn part2((grid, curr_pos, moves): &(Grid, Move, Vec<Move>)) -> usize {
-    let mut grid = grid.clone();
-    let mut robot_pos = *curr_pos;
+fn parse(input: &str, box_width: usize) -> (Warehouse, Vec<Move>) {
+    let (grid_input, moves_input) = input.split_once("\n\n").unwrap();
+    (
+        Warehouse::new(grid_input, box_width),
+        parse_moves(moves_input),
+    )
+}
+
+#[aoc_generator(day15, part1)]
+fn parse1(input: &str) -> (Warehouse, Vec<Move>) {
+    parse(input, 1)
+}
+
+#[aoc_generator(day15, part2)]
+fn parse2(input: &str) -> (Warehouse, Vec<Move>) {
+    parse(input, 2)
+}
+
+fn simulate(warehouse: &Warehouse, moves: &[Move]) -> usize {
+    let mut warehouse = warehouse.clone();
     for mv in moves.iter() {
-        let mut move_ok = true;
-        move_robot(&mut robot_pos, mv, &mut grid, &mut move_ok);
+        warehouse.step(mv);
     }
-    compute_gps(&grid, '[')
+    warehouse.gps()
+}
+
+#[aoc(day15, part1)]
+fn part1((warehouse, moves): &(Warehouse, Vec<Move>)) -> usize {
+    simulate(warehouse, moves)
+}
+
+#[aoc(day15, part2)]
+fn part2((warehouse, moves): &(Warehouse, Vec<Move>)) -> usize {
+    simulate(warehouse, moves)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "##########\n\
-                                 #..O..O.O#\n\
-                                 #......O.#\n\
-                                 #.OO..O.O#\n\
-                                 #..O@..O.#\n\
-                                 #O#..O...#\n\
-                                 #O..O..O.#\n\
-                                 #.OO.O.OO#\n\
-                                 #....O...#\n\
-                                 ##########\n\
-                                 \n\
-                                 <vv>^<v^>v>^vv^v>v<>v^v<v<^vv<<<^><<><>>v<vvv<>^v^>^<<<><<v<<<v^vv^v>^\n\
-                                 vvv<<^>^v^^><<>>><>^<<><^vv^^<>vvv<>><^^v>^>vv<>v<<<<v<^v>^<^^>>>^<v<v\n\
-                                 ><>vv>v^v^<>><>>>><^^>vv>v<^^^>>v^v^<^^>v^^>v^<^v>v<>>v^v^<v>v^^<^^vv<\n\
-                                 <<v<^>>^^^^>>>v^<>vvv^><v<<<>^^^vv^<vvv>^>v<^^^^v<>^>vvvv><>>v^<<^^^^^\n\
-                                 ^><^><>>><>^^<<^^v>>><^<v>^<vv>>v>>>^v><>^v><<<<v>>v<v<v>vvv>^<><<>^><\n\
-                                 ^>><>^v<><^vvv<^^<><v<<<<<><^v<<<><<<^^<v<^^^><^>>^<v^><<<^>>^v<v^v<v^\n\
-                                 >^>>^v>vv>^<<^v<>><<><<v<<v><>v<^vv<<<>^^v^>^^>>><<^v>>v^v><^^>>^<>vv^\n\
-                                 <><^^>^^^<><vvvvv^v<v<<>^v<v>v<<^><<><<><<<^^<<<^<<>><<><^^^>^^<>^>v<>\n\
-                                 ^^>vv<^v^v<vv>^<><v<^v>^^^>>>^^vvv^>vvv<>>>^<^>>>>>^<<^v>^vvv<>^<><<v>\n\
-                                 v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^";
+    const INPUT: &str = "##########\n\
+                         #..O..O.O#\n\
+                         #......O.#\n\
+                         #.OO..O.O#\n\
+                         #..O@..O.#\n\
+                         #O#..O...#\n\
+                         #O..O..O.#\n\
+                         #.OO.O.OO#\n\
+                         #....O...#\n\
+                         ##########\n\
+                         \n\
+                         <vv>^<v^>v>^vv^v>v<>v^v<v<^vv<<<^><<><>>v<vvv<>^v^>^<<<><<v<<<v^vv^v>^\n\
+                         vvv<<^>^v^^><<>>><>^<<><^vv^^<>vvv<>><^^v>^>vv<>v<<<<v<^v>^<^^>>>^<v<v\n\
+                         ><>vv>v^v^<>><>>>><^^>vv>v<^^^>>v^v^<^^>v^^>v^<^v>v<>>v^v^<v>v^^<^^vv<\n\
+                         <<v<^>>^^^^>>>v^<>vvv^><v<<<>^^^vv^<vvv>^>v<^^^^v<>^>vvvv><>>v^<<^^^^^\n\
+                         ^><^><>>><>^^<<^^v>>><^<v>^<vv>>v>>>^v><>^v><<<<v>>v<v<v>vvv>^<><<>^><\n\
+                         ^>><>^v<><^vvv<^^<><v<<<<<><^v<<<><<<^^<v<^^^><^>>^<v^><<<^>>^v<v^v<v^\n\
+                         >^>>^v>vv>^<<^v<>><<><<v<<v><>v<^vv<<<>^^v^>^^>>><<^v>>v^v><^^>>^<>vv^\n\
+                         <><^^>^^^<><vvvvv^v<v<<>^v<v>v<<^><<><<><<<^^<<<^<<>><<><^^^>^^<>^>v<>\n\
+                         ^^>vv<^v^v<vv>^<><v<^v>^^^>>>^^vvv^>vvv<>>>^<^>>>>>^<<^v>^vvv<>^<><<v>\n\
+                         v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^";
 
     #[test]
     fn part1_example() {
@@ -248,57 +618,249 @@ mod tests {
         assert_eq!(part2(&parse2(INPUT)), 9021);
     }
 
-    const INPUT2: &'static str = "#######\n\
-                                 #...#.#\n\
-                                 #.....#\n\
-                                 #.....#\n\
-                                 #.....#\n\
-                                 #.....#\n\
-                                 #.OOO@#\n\
-                                 #.OOO.#\n\
-                                 #..O..#\n\
-                                 #.....#\n\
-                                 #.....#\n\
-                                 #######\n\
-                                 \n\
-                                 v<vv<<^^^^^";
+    const INPUT2: &str = "#######\n\
+                          #...#.#\n\
+                          #.....#\n\
+                          #.....#\n\
+                          #.....#\n\
+                          #.....#\n\
+                          #.OOO@#\n\
+                          #.OOO.#\n\
+                          #..O..#\n\
+                          #.....#\n\
+                          #.....#\n\
+                          #######\n\
+                          \n\
+                          v<vv<<^^^^^";
 
     #[test]
     fn part2_example2() {
         assert_eq!(part2(&parse2(INPUT2)), 2339);
     }
 
-    const INPUT3: &'static str = "########\n\
-                                 #......#\n\
-                                 #OO....#\n\
-                                 #.O....#\n\
-                                 #.O....#\n\
-                                 ##O....#\n\
-                                 #O..O@.#\n\
-                                 #......#\n\
-                                 ########\n\
-                                 \n\
-                                 <^^<<>^^^<v";
+    const INPUT3: &str = "########\n\
+                          #......#\n\
+                          #OO....#\n\
+                          #.O....#\n\
+                          #.O....#\n\
+                          ##O....#\n\
+                          #O..O@.#\n\
+                          #......#\n\
+                          ########\n\
+                          \n\
+                          <^^<<>^^^<v";
 
     #[test]
     fn part2_example3() {
         assert_eq!(part2(&parse2(INPUT3)), 2827);
     }
 
-    const INPUT4: &'static str = "######\n\
-                                 #....#\n\
-                                 #..#.#\n\
-                                 #....#\n\
-                                 #.O..#\n\
-                                 #.OO@#\n\
-                                 #.O..#\n\
-                                 #....#\n\
-                                 ######\n\
-                                 \n\
-                                 <vv<<^^^";
+    const INPUT4: &str = "######\n\
+                          #....#\n\
+                          #..#.#\n\
+                          #....#\n\
+                          #.O..#\n\
+                          #.OO@#\n\
+                          #.O..#\n\
+                          #....#\n\
+                          ######\n\
+                          \n\
+                          <vv<<^^^";
 
     #[test]
     fn part2_example4() {
         assert_eq!(part2(&parse2(INPUT4)), 1216);
     }
+
+    #[test]
+    fn render_wide_boxes() {
+        let (grid_input, _) = INPUT4.split_once("\n\n").unwrap();
+        let expected = "############\n\
+                        ##........##\n\
+                        ##....##..##\n\
+                        ##........##\n\
+                        ##..[]....##\n\
+                        ##..[][]@.##\n\
+                        ##..[]....##\n\
+                        ##........##\n\
+                        ############";
+        assert_eq!(Warehouse::new(grid_input, 2).to_string(), expected);
+        assert_eq!(Warehouse::new(grid_input, 1).to_string(), grid_input);
+    }
+
+    #[test]
+    fn three_wide_boxes() {
+        let (mut warehouse, moves) = parse(INPUT4, 3);
+        for mv in moves.iter() {
+            warehouse.step(mv);
+        }
+        let expected = "##################\n\
+                        ###............###\n\
+                        ###...[-]###...###\n\
+                        ###.....[-]....###\n\
+                        ###......@.....###\n\
+                        ###..[-].......###\n\
+                        ###...[-]......###\n\
+                        ###............###\n\
+                        ##################";
+        assert_eq!(warehouse.to_string(), expected);
+    }
+
+    #[test]
+    fn replay_frames() {
+        let (warehouse, moves) = parse2(INPUT2);
+        let replay = Replay::record(&warehouse, &moves);
+        assert_eq!(replay.len(), moves.len());
+        assert_eq!(replay.frame(0), warehouse.to_grid());
+        for n in 1..=moves.len() {
+            let mut expected = warehouse.clone();
+            for mv in moves[..n].iter() {
+                expected.step(mv);
+            }
+            assert_eq!(replay.frame(n), expected.to_grid());
+        }
+    }
+
+    #[test]
+    fn replay_exports() {
+        let (warehouse, moves) = parse2(INPUT4);
+        let replay = Replay::record(&warehouse, &moves);
+        // first move pushes the two boxes on the robot row to the left
+        assert_eq!(
+            replay.delta(1),
+            &vec![
+                ((5, 8), '.'),
+                ((5, 5), '['),
+                ((5, 6), ']'),
+                ((5, 7), '@'),
+                ((5, 3), '['),
+                ((5, 4), ']')
+            ]
+        );
+        let log = replay.delta_log();
+        assert_eq!(log.lines().count(), moves.len());
+        assert!(log.starts_with("1 < 5,8=. "));
+
+        let animation = replay.animation();
+        assert_eq!(animation.matches("\nMove ").count(), moves.len());
+        assert!(animation.ends_with(&format!("{}\n", grid_to_string(&replay.frame(moves.len())))));
+    }
+
+    #[test]
+    fn undo_redo() {
+        let (warehouse, moves) = parse2(INPUT);
+        let mut warehouse = warehouse.with_history();
+        let initial = warehouse.to_string();
+        let mut states = vec![initial.clone()];
+        for mv in moves.iter() {
+            warehouse.step(mv);
+            states.push(warehouse.to_string());
+        }
+        assert_eq!(warehouse.gps(), 9021);
+        assert_eq!(warehouse.history_len(), moves.len());
+
+        // walk all the way back, then forward again
+        for n in (0..moves.len()).rev() {
+            assert!(warehouse.undo());
+            assert_eq!(warehouse.to_string(), states[n]);
+        }
+        assert!(!warehouse.undo());
+        for state in states.iter().skip(1) {
+            assert!(warehouse.redo());
+            assert_eq!(&warehouse.to_string(), state);
+        }
+        assert!(!warehouse.redo());
+        assert_eq!(warehouse.gps(), 9021);
+    }
+
+    #[test]
+    fn step_clears_redo() {
+        let (warehouse, moves) = parse1(INPUT);
+        let mut warehouse = warehouse.with_history();
+        warehouse.step(&moves[0]);
+        warehouse.undo();
+        warehouse.step(&moves[1]);
+        assert!(!warehouse.redo());
+        assert_eq!(warehouse.history_len(), 1);
+    }
+
+    #[test]
+    fn no_history_by_default() {
+        let (mut warehouse, moves) = parse1(INPUT);
+        for mv in moves.iter() {
+            warehouse.step(mv);
+        }
+        assert_eq!(warehouse.gps(), 10092);
+        assert_eq!(warehouse.history_len(), 0);
+        assert!(!warehouse.undo());
+    }
+
+    #[test]
+    fn checked_simulation() {
+        for (warehouse, moves) in [
+            parse1(INPUT),
+            parse2(INPUT),
+            parse2(INPUT2),
+            parse(INPUT4, 3),
+        ] {
+            let expected = simulate(&warehouse, &moves);
+            assert_eq!(
+                simulate_checked(&warehouse, &moves).unwrap().gps(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn check_grid_violations() {
+        let (warehouse, _) = parse2(INPUT4);
+        let initial = warehouse.to_grid();
+        assert_eq!(check_grid(&initial, &initial), Ok(()));
+
+        let mut grid = initial.clone();
+        grid[5][5] = '.';
+        assert_eq!(
+            check_grid(&grid, &initial),
+            Err(Violation::OrphanBoxEdge((5, 4)))
+        );
+
+        let mut grid = initial.clone();
+        grid[6][5] = '[';
+        assert_eq!(
+            check_grid(&grid, &initial),
+            Err(Violation::OrphanBoxEdge((6, 4)))
+        );
+
+        let mut grid = initial.clone();
+        grid[2][6] = '.';
+        assert_eq!(
+            check_grid(&grid, &initial),
+            Err(Violation::WallChanged((2, 6)))
+        );
+
+        let mut grid = initial.clone();
+        grid[1][2] = '@';
+        assert_eq!(check_grid(&grid, &initial), Err(Violation::RobotCount(2)));
+
+        let mut grid = initial.clone();
+        grid[1][2] = '[';
+        grid[1][3] = ']';
+        assert_eq!(
+            check_grid(&grid, &initial),
+            Err(Violation::BoxCount {
+                expected: 4,
+                found: 5
+            })
+        );
+    }
+
+    #[test]
+    fn check_box_in_wall() {
+        let (mut warehouse, _) = parse2(INPUT4);
+        let initial = warehouse.to_grid();
+        // simulate a bug pushing the box left of the robot into the wall
+        warehouse.step(&(0, -1));
+        warehouse.boxes[0].position = (1, 1);
+        assert_eq!(warehouse.check(&initial), Err(Violation::InWall((1, 1))));
+    }
 }
@@ -1,3 +1,4 @@
+use crate::grid::grid_to_string;
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashSet};
@@ -26,33 +27,87 @@ impl PartialOrd for State {
     }
 }
 
-/// Struct to keep track of the actual path
+/// A tile of a path, with the direction the reindeer faces when entering it
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct Step {
+    pub position: [usize; 2],
+    pub direction: [isize; 2],
+}
+
+/// A complete path from start to end
 #[derive(Clone, Eq, PartialEq, Debug)]
-struct StateWAnc {
-    cost: usize,
-    position: [usize; 2],
-    direction: [isize; 2],
-    ancestors: Vec<[usize; 2]>,
+pub struct Path {
+    pub cost: usize,
+    pub steps: Vec<Step>,
 }
 
-impl Ord for StateWAnc {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other
-            .cost
-            .cmp(&self.cost)
-            .then_with(|| self.position.cmp(&other.position))
+impl Path {
+    /// Number of 90 degrees rotations along the path
+    pub fn turns(&self) -> usize {
+        self.steps
+            .windows(2)
+            .map(|w| {
+                let [a, b] = [w[0].direction, w[1].direction];
+                if a == b {
+                    0
+                } else if a == [-b[0], -b[1]] {
+                    2
+                } else {
+                    1
+                }
+            })
+            .sum()
     }
 }
 
-impl PartialOrd for StateWAnc {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// Direction the reindeer can face
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Default)]
+pub enum Heading {
+    #[default]
+    East,
+    West,
+    South,
+    North,
+}
+
+impl Heading {
+    /// Move of one tile in this direction, as (row, column)
+    pub fn delta(&self) -> [isize; 2] {
+        match self {
+            Heading::East => [0, 1],
+            Heading::West => [0, -1],
+            Heading::South => [1, 0],
+            Heading::North => [-1, 0],
+        }
     }
 }
 
+/// Cost of each kind of move in the maze
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct CostModel {
+    /// Moving forward by one tile
+    pub step: usize,
+    /// Rotating 90 degrees, always followed by a step
+    pub turn: usize,
+    /// Whether we can rotate 180 degrees (costing two turns) and step back
+    pub u_turn: bool,
+    /// Direction faced on the start tile
+    pub start_heading: Heading,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            step: 1,
+            turn: 1000,
+            u_turn: false,
+            start_heading: Heading::East,
+        }
+    }
+}
 
 #[aoc_generator(day16)]
-fn parse(input: &str) -> (Grid, [usize; 2], [usize; 2]) {
+pub fn parse(input: &str) -> (Grid, [usize; 2], [usize; 2]) {
     let n_rows = input.lines().count();
     let n_cols = input.lines().next().unwrap().chars().count();
     let mut grid: Grid = vec![vec!['#'; n_cols]; n_rows];
@@ -77,13 +132,21 @@ fn rotate(dir: &[isize; 2]) -> [[isize; 2]; 3] {
     match *dir {
         [0, 1] | [0, -1] => [*dir, [-1, 0], [1, 0]],
         [1, 0] | [-1, 0] => [*dir, [0, -1], [0, 1]],
-        _ => [[0, 0], [0, 0], [0, 0]],
+        _ => panic!("bad direction given"),
     }
 }
-fn find_neighbor(curr_state: &State, grid: &Grid) -> Vec<State> {
-    let possib_move = rotate(&curr_state.direction);
+fn find_neighbor(curr_state: &State, grid: &Grid, costs: &CostModel) -> Vec<State> {
+    let dir = curr_state.direction;
+    let mut possib_move: Vec<([isize; 2], usize)> = rotate(&dir)
+        .iter()
+        .enumerate()
+        .map(|(i, mov)| (*mov, if i == 0 { 0 } else { costs.turn }))
+        .collect();
+    if costs.u_turn {
+        possib_move.push(([-dir[0], -dir[1]], 2 * costs.turn));
+    }
     let mut moves: Vec<State> = Vec::new();
-    for (i, mov) in possib_move.iter().enumerate() {
+    for (mov, turn_cost) in possib_move.iter() {
         let nxt_pos = [
             curr_state.position[0] as isize + mov[0],
             curr_state.position[1] as isize + mov[1],
@@ -92,65 +155,134 @@ fn find_neighbor(curr_state: &State, grid: &Grid) -> Vec<State> {
             moves.push(State {
                 position: [nxt_pos[0] as usize, nxt_pos[1] as usize],
                 direction: [mov[0], mov[1]],
-                cost: if i == 0 {
-                    curr_state.cost + 1
-                } else {
-                    curr_state.cost + 1001
-                },
+                cost: curr_state.cost + turn_cost + costs.step,
             });
         }
     }
     moves
 }
 
-
-fn check_cost(sub_dist: &[usize; 4], dir: &[isize; 2]) -> usize {
+fn dir_index(dir: &[isize; 2]) -> usize {
     match *dir {
-        [0, 1] => sub_dist[0],
-        [0, -1] => sub_dist[1],
-        [1, 0] => sub_dist[2],
-        [-1, 0] => sub_dist[3],
+        [0, 1] => 0,
+        [0, -1] => 1,
+        [1, 0] => 2,
+        [-1, 0] => 3,
         _ => panic!("bad direction given"),
     }
 }
+
+fn check_cost(sub_dist: &[usize; 4], dir: &[isize; 2]) -> usize {
+    sub_dist[dir_index(dir)]
+}
 fn update_cost(sub_dist: &mut [usize; 4], dir: &[isize; 2], value: usize) {
-    match *dir {
-        [0, 1] => {
-            sub_dist[0] = value;
-        }
-        [0, -1] => {
-            sub_dist[1] = value;
-        }
-        [1, 0] => {
-            sub_dist[2] = value;
-        }
-        [-1, 0] => {
-            sub_dist[3] = value;
-        }
-        _ => panic!("bad direction given"),
+    sub_dist[dir_index(dir)] = value;
+}
+
+/// State ordered by its estimated total cost (cost so far plus heuristic)
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Estimate {
+    estimate: usize,
+    state: State,
+}
+
+impl Ord for Estimate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // on ties, prefer the state which went the furthest
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.state.cost.cmp(&other.state.cost))
+            .then_with(|| self.state.position.cmp(&other.state.position))
     }
 }
 
-#[aoc(day16, part1)]
-fn part1((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
+impl PartialOrd for Estimate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Lower bound of the cost to reach `end`: Manhattan distance plus the
+/// minimum number of turns needed given the current direction.
+fn min_remaining_cost(state: &State, end: &[usize; 2], costs: &CostModel) -> usize {
+    let delta = [
+        end[0] as isize - state.position[0] as isize,
+        end[1] as isize - state.position[1] as isize,
+    ];
+    let dir = state.direction;
+    // how far we move toward the target along each axis when going forward
+    let toward = [delta[0] * dir[0], delta[1] * dir[1]];
+    let turns = if delta == [0, 0] {
+        0
+    } else if delta[0] == 0 || delta[1] == 0 {
+        // target on the same row or column
+        if toward[0] > 0 || toward[1] > 0 {
+            0
+        } else if toward[0] < 0 || toward[1] < 0 {
+            2
+        } else {
+            1
+        }
+    } else if toward[0] > 0 || toward[1] > 0 {
+        1
+    } else {
+        2
+    };
+    (delta[0].unsigned_abs() + delta[1].unsigned_abs()) * costs.step + turns * costs.turn
+}
+
+/// Outcome of a search from start to end
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Lowest score, `None` if the end cannot be reached
+    pub score: Option<usize>,
+    /// Number of states taken out of the heap and expanded
+    pub expanded: usize,
+}
+
+/// Best-first search guided by `heuristic`, which must never overestimate
+/// the remaining cost.
+fn search<H: Fn(&State) -> usize>(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+    heuristic: H,
+) -> SearchResult {
     let n_rows = grid.len();
     let n_cols = grid[0].len();
 
     // for each point there is 4 possible incoming directions
     let mut dist = vec![vec![[usize::MAX; 4]; n_cols]; n_rows];
-    dist[start[0]][start[1]] = [0, 0, 0, 0];
+    update_cost(
+        &mut dist[start[0]][start[1]],
+        &costs.start_heading.delta(),
+        0,
+    );
 
-    let mut heap = BinaryHeap::new();
-    heap.push(State {
+    let start_state = State {
         cost: 0,
         position: *start,
-        direction: [0, 1],
+        direction: costs.start_heading.delta(),
+    };
+    let mut heap = BinaryHeap::new();
+    heap.push(Estimate {
+        estimate: heuristic(&start_state),
+        state: start_state,
     });
 
-    while let Some(curr_state) = heap.pop() {
+    let mut expanded = 0;
+    while let Some(Estimate {
+        state: curr_state, ..
+    }) = heap.pop()
+    {
         // first shortest path to target found
         if &curr_state.position == end {
-            return curr_state.cost;
+            return SearchResult {
+                score: Some(curr_state.cost),
+                expanded,
+            };
         }
 
         // Important as we may have already found a better way
@@ -161,11 +293,12 @@ fn part1((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
         if curr_state.cost > prev_cost {
             continue;
         }
+        expanded += 1;
 
         // For each node we can reach, see if we can find a way with
         // a lower cost going through this node
-        let edges = find_neighbor(&curr_state, grid);
-        for next in edges.iter() {
+        let edges = find_neighbor(&curr_state, grid, costs);
+        for next in edges.into_iter() {
             // If so, add it to the frontier and continue
             let nxt_cost = check_cost(&dist[next.position[0]][next.position[1]], &next.direction);
             if next.cost < nxt_cost {
@@ -175,66 +308,110 @@ fn part1((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
                     &next.direction,
                     next.cost,
                 );
-                heap.push(next.clone());
+                heap.push(Estimate {
+                    estimate: next.cost + heuristic(&next),
+                    state: next,
+                });
             }
         }
     }
-    // If we reach this point, means no path to target was found.
-    // Should not happen.
-    panic!("No path to target found!");
+    // no path to target
+    SearchResult {
+        score: None,
+        expanded,
+    }
 }
 
-/// find neighbors and keep track of ancestors
-fn find_neighbor_with_anc(curr_state: &StateWAnc, grid: &Grid) -> Vec<StateWAnc> {
-    let possib_move = rotate(&curr_state.direction);
-    let mut moves: Vec<StateWAnc> = Vec::new();
-    for (i, mov) in possib_move.iter().enumerate() {
-        let nxt_pos = [
-            curr_state.position[0] as isize + mov[0],
-            curr_state.position[1] as isize + mov[1],
-        ];
-        if grid[nxt_pos[0] as usize][nxt_pos[1] as usize] != '#' {
-            let mut new_ancestors = curr_state.ancestors.clone();
-            new_ancestors.push(curr_state.position);
-            moves.push(StateWAnc {
-                position: [nxt_pos[0] as usize, nxt_pos[1] as usize],
-                direction: [mov[0], mov[1]],
-                cost: if i == 0 {
-                    curr_state.cost + 1
-                } else {
-                    curr_state.cost + 1001
-                },
-                ancestors: new_ancestors,
-            });
-        }
-    }
-    moves
+/// Search from start to end with Dijkstra
+pub fn dijkstra(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+) -> SearchResult {
+    search(grid, start, end, costs, |_| 0)
 }
 
-#[aoc(day16, part2)]
-fn part2((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
+/// Search from start to end with A*, using a turn-aware heuristic
+pub fn astar(grid: &Grid, start: &[usize; 2], end: &[usize; 2], costs: &CostModel) -> SearchResult {
+    search(grid, start, end, costs, |state| {
+        min_remaining_cost(state, end, costs)
+    })
+}
+
+/// Lowest score to go from start to end, using Dijkstra,
+/// or `None` if the end cannot be reached
+pub fn lowest_score(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+) -> Option<usize> {
+    dijkstra(grid, start, end, costs).score
+}
+
+/// Lowest score to go from start to end, using A* with a turn-aware heuristic,
+/// or `None` if the end cannot be reached
+pub fn lowest_score_astar(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+) -> Option<usize> {
+    astar(grid, start, end, costs).score
+}
+
+#[aoc(day16, part1)]
+fn part1((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
+    lowest_score(grid, start, end, &CostModel::default()).expect("No path to target found!")
+}
+
+#[aoc(day16, part1, astar)]
+fn part1_astar((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
+    lowest_score_astar(grid, start, end, &CostModel::default()).expect("No path to target found!")
+}
+
+/// Best predecessors of every (position, direction) state
+type Predecessors = Vec<Vec<[Vec<Step>; 4]>>;
+
+/// Dijkstra that records, for each state, all the states it can be reached from
+/// with the lowest cost. Stops once every state cheaper than the end is settled.
+/// Returns the predecessors and the best score at the end tile,
+/// or `None` if the end cannot be reached.
+fn best_predecessors(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+) -> Option<(Predecessors, [usize; 4], usize)> {
     let n_rows = grid.len();
     let n_cols = grid[0].len();
 
     // for each point there is 4 possible incoming directions
     let mut dist = vec![vec![[usize::MAX; 4]; n_cols]; n_rows];
-    dist[start[0]][start[1]] = [0, 0, 0, 0];
+    update_cost(
+        &mut dist[start[0]][start[1]],
+        &costs.start_heading.delta(),
+        0,
+    );
+    let mut preds: Predecessors = vec![vec![Default::default(); n_cols]; n_rows];
 
     let mut heap = BinaryHeap::new();
-    heap.push(StateWAnc {
+    heap.push(State {
         cost: 0,
         position: *start,
-        direction: [0, 1],
-        ancestors: Vec::new(),
+        direction: costs.start_heading.delta(),
     });
 
-    let mut path_to_end: Vec<Vec<[usize; 2]>> = Vec::new();
-    let mut path_scores: Vec<usize> = Vec::new();
-
+    let mut best_score = usize::MAX;
     while let Some(curr_state) = heap.pop() {
+        // every remaining state is more expensive than the best path
+        if curr_state.cost > best_score {
+            break;
+        }
         if &curr_state.position == end {
-            path_to_end.push(curr_state.ancestors.clone());
-            path_scores.push(curr_state.cost);
+            best_score = curr_state.cost;
+            continue;
         }
 
         let prev_cost = check_cost(
@@ -245,83 +422,191 @@ fn part2((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
             continue;
         }
 
-        let edges = find_neighbor_with_anc(&curr_state, grid);
-        for next in edges.iter() {
-            // If so, add it to the frontier and continue
-            let nxt_cost = check_cost(&dist[next.position[0]][next.position[1]], &next.direction);
-            // relax condition here to find all path with same cost
-            if next.cost <= nxt_cost {
-                update_cost(
-                    &mut dist[next.position[0]][next.position[1]],
-                    &next.direction,
-                    next.cost,
-                );
-                heap.push(next.clone());
+        let curr_step = Step {
+            position: curr_state.position,
+            direction: curr_state.direction,
+        };
+        for next in find_neighbor(&curr_state, grid, costs) {
+            let [i, j] = next.position;
+            let nxt_cost = check_cost(&dist[i][j], &next.direction);
+            let nxt_preds = &mut preds[i][j][dir_index(&next.direction)];
+            if next.cost < nxt_cost {
+                // strictly better: previous predecessors are not optimal anymore
+                update_cost(&mut dist[i][j], &next.direction, next.cost);
+                nxt_preds.clear();
+                nxt_preds.push(curr_step);
+                heap.push(next);
+            } else if next.cost == nxt_cost {
+                // another way to get here with the same cost
+                nxt_preds.push(curr_step);
             }
         }
     }
+    if best_score == usize::MAX {
+        return None;
+    }
+    Some((preds, dist[end[0]][end[1]], best_score))
+}
 
-    let best_score = dist[end[0]][end[1]].iter().min().unwrap();
+/// States at the end tile reached with the best score
+fn end_steps(end: &[usize; 2], end_dist: &[usize; 4], best_score: usize) -> Vec<Step> {
+    [[0, 1], [0, -1], [1, 0], [-1, 0]]
+        .into_iter()
+        .filter(|dir| check_cost(end_dist, dir) == best_score)
+        .map(|direction| Step {
+            position: *end,
+            direction,
+        })
+        .collect()
+}
 
-    // Find unique tiles from path_to_end
-    let mut tiles: HashSet<[usize; 2]> = HashSet::new();
-    for (path, score) in path_to_end.iter().zip(path_scores) {
-        // we only care for paths with best score
-        if &score == best_score {
-            for pos in path.iter() {
-                tiles.insert(*pos);
-            }
+/// Walk back the predecessors, building every path ending with `suffix`
+fn collect_paths(preds: &Predecessors, suffix: &mut Vec<Step>, paths: &mut Vec<Vec<Step>>) {
+    let last = suffix.last().unwrap();
+    let prev = &preds[last.position[0]][last.position[1]][dir_index(&last.direction)];
+    if prev.is_empty() {
+        // we are back at the start
+        paths.push(suffix.iter().rev().copied().collect());
+        return;
+    }
+    for step in prev.iter() {
+        suffix.push(*step);
+        collect_paths(preds, suffix, paths);
+        suffix.pop();
+    }
+}
+
+/// Find every path from start to end with the lowest score,
+/// none if the end cannot be reached
+pub fn optimal_paths(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+) -> Vec<Path> {
+    let Some((preds, end_dist, best_score)) = best_predecessors(grid, start, end, costs) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<Vec<Step>> = Vec::new();
+    for step in end_steps(end, &end_dist, best_score) {
+        collect_paths(&preds, &mut vec![step], &mut paths);
+    }
+    paths
+        .into_iter()
+        .map(|steps| Path {
+            cost: best_score,
+            steps,
+        })
+        .collect()
+}
+
+fn direction_arrow(dir: &[isize; 2]) -> char {
+    match *dir {
+        [0, 1] => '>',
+        [0, -1] => '<',
+        [1, 0] => 'v',
+        [-1, 0] => '^',
+        _ => panic!("bad direction given"),
+    }
+}
+
+/// Draw a single path on the maze, each tile showing the direction we leave it
+pub fn render_path(grid: &Grid, path: &Path) -> String {
+    let mut canvas = grid.clone();
+    for w in path.steps.windows(2) {
+        let [i, j] = w[0].position;
+        if canvas[i][j] == '.' {
+            canvas[i][j] = direction_arrow(&w[1].direction);
+        }
+    }
+    grid_to_string(&canvas)
+}
+
+/// Mark with `O` every tile that is part of at least one of the paths
+pub fn render_tiles(grid: &Grid, paths: &[Path]) -> String {
+    let mut canvas = grid.clone();
+    for step in paths.iter().flat_map(|path| path.steps.iter()) {
+        canvas[step.position[0]][step.position[1]] = 'O';
+    }
+    grid_to_string(&canvas)
+}
+
+/// Number of tiles that are part of at least one best path,
+/// or `None` if the end cannot be reached
+pub fn best_tiles(
+    grid: &Grid,
+    start: &[usize; 2],
+    end: &[usize; 2],
+    costs: &CostModel,
+) -> Option<usize> {
+    let (preds, end_dist, best_score) = best_predecessors(grid, start, end, costs)?;
+
+    // Backtrack from the end, visiting each state only once
+    let mut seen: HashSet<Step> = HashSet::new();
+    let mut stack = end_steps(end, &end_dist, best_score);
+    while let Some(step) = stack.pop() {
+        if !seen.insert(step) {
+            continue;
         }
+        let prev = &preds[step.position[0]][step.position[1]][dir_index(&step.direction)];
+        stack.extend(prev.iter().copied());
     }
-    // we add one because the target tile is not included
-    tiles.len() + 1
+
+    // Find unique tiles on the optimal paths
+    let tiles: HashSet<[usize; 2]> = seen.iter().map(|step| step.position).collect();
+    Some(tiles.len())
+}
+
+#[aoc(day16, part2)]
+fn part2((grid, start, end): &(Grid, [usize; 2], [usize; 2])) -> usize {
+    best_tiles(grid, start, end, &CostModel::default()).expect("No path to target found!")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT1: &'static str = "###############\n\
-                                  #.......#....E#\n\
-                                  #.#.###.#.###.#\n\
-                                  #.....#.#...#.#\n\
-                                  #.###.#####.#.#\n\
-                                  #.#.#.......#.#\n\
-                                  #.#.#####.###.#\n\
-                                  #...........#.#\n\
-                                  ###.#.#####.#.#\n\
-                                  #...#.....#.#.#\n\
-                                  #.#.#.###.#.#.#\n\
-                                  #.....#...#.#.#\n\
-                                  #.###.#.#.#.#.#\n\
-                                  #S..#.....#...#\n\
-                                  ###############";
-
-    const INPUT2: &'static str = "#################\n\
-                                  #...#...#...#..E#\n\
-                                  #.#.#.#.#.#.#.#.#\n\
-                                  #.#.#.#...#...#.#\n\
-                                  #.#.#.#.###.#.#.#\n\
-                                  #...#.#.#.....#.#\n\
-                                  #.#.#.#.#.#####.#\n\
-                                  #.#...#.#.#.....#\n\
-                                  #.#.#####.#.###.#\n\
-                                  #.#.#.......#...#\n\
-                                  #.#.###.#####.###\n\
-                                  #.#.#...#.....#.#\n\
-                                  #.#.#.#####.###.#\n\
-                                  #.#.#.........#.#\n\
-                                  #.#.#.#########.#\n\
-                                  #S#.............#\n\
-                                  #################";
-
-    const INPUT3: &'static str = "##########\n\
-                                  #.......E#\n\
-                                  #.##.#####\n\
-                                  #..#.....#\n\
-                                  ##.#####.#\n\
-                                  #S.......#\n\
-                                  ##########";
+    const INPUT1: &str = "###############\n\
+                          #.......#....E#\n\
+                          #.#.###.#.###.#\n\
+                          #.....#.#...#.#\n\
+                          #.###.#####.#.#\n\
+                          #.#.#.......#.#\n\
+                          #.#.#####.###.#\n\
+                          #...........#.#\n\
+                          ###.#.#####.#.#\n\
+                          #...#.....#.#.#\n\
+                          #.#.#.###.#.#.#\n\
+                          #.....#...#.#.#\n\
+                          #.###.#.#.#.#.#\n\
+                          #S..#.....#...#\n\
+                          ###############";
+
+    const INPUT2: &str = "#################\n\
+                          #...#...#...#..E#\n\
+                          #.#.#.#.#.#.#.#.#\n\
+                          #.#.#.#...#...#.#\n\
+                          #.#.#.#.###.#.#.#\n\
+                          #...#.#.#.....#.#\n\
+                          #.#.#.#.#.#####.#\n\
+                          #.#...#.#.#.....#\n\
+                          #.#.#####.#.###.#\n\
+                          #.#.#.......#...#\n\
+                          #.#.###.#####.###\n\
+                          #.#.#...#.....#.#\n\
+                          #.#.#.#####.###.#\n\
+                          #.#.#.........#.#\n\
+                          #.#.#.#########.#\n\
+                          #S#.............#\n\
+                          #################";
+
+    const INPUT3: &str = "##########\n\
+                          #.......E#\n\
+                          #.##.#####\n\
+                          #..#.....#\n\
+                          ##.#####.#\n\
+                          #S.......#\n\
+                          ##########";
 
     #[test]
     fn part1_example1() {
@@ -352,4 +637,162 @@ mod tests {
     fn part2_example3() {
         assert_eq!(part2(&parse(INPUT3)), 14);
     }
+
+    #[test]
+    fn optimal_paths_example1() {
+        let (grid, start, end) = parse(INPUT1);
+        let paths = optimal_paths(&grid, &start, &end, &CostModel::default());
+        assert_eq!(paths.len(), 3);
+        for path in paths.iter() {
+            assert_eq!(path.cost, 7036);
+            assert_eq!(path.steps.first().unwrap().position, start);
+            assert_eq!(path.steps.last().unwrap().position, end);
+            assert_eq!(path.steps.len() - 1 + 1000 * path.turns(), path.cost);
+        }
+    }
+
+    #[test]
+    fn render_tiles_example1() {
+        let (grid, start, end) = parse(INPUT1);
+        let paths = optimal_paths(&grid, &start, &end, &CostModel::default());
+        let expected = "###############\n\
+                        #.......#....O#\n\
+                        #.#.###.#.###O#\n\
+                        #.....#.#...#O#\n\
+                        #.###.#####.#O#\n\
+                        #.#.#.......#O#\n\
+                        #.#.#####.###O#\n\
+                        #..OOOOOOOOO#O#\n\
+                        ###O#O#####O#O#\n\
+                        #OOO#O....#O#O#\n\
+                        #O#O#O###.#O#O#\n\
+                        #OOOOO#...#O#O#\n\
+                        #O###.#.#.#O#O#\n\
+                        #O..#.....#OOO#\n\
+                        ###############";
+        assert_eq!(render_tiles(&grid, &paths), expected);
+    }
+
+    #[test]
+    fn render_path_example3() {
+        let (grid, start, end) = parse(INPUT3);
+        let paths = optimal_paths(&grid, &start, &end, &CostModel::default());
+        assert_eq!(paths.len(), 1);
+        let expected = "##########\n\
+                        #>>>>>>>E#\n\
+                        #^##.#####\n\
+                        #^<#.....#\n\
+                        ##^#####.#\n\
+                        #S^......#\n\
+                        ##########";
+        assert_eq!(render_path(&grid, &paths[0]), expected);
+    }
+
+    #[test]
+    fn optimal_paths_match_part2() {
+        let input = parse(INPUT2);
+        let paths = optimal_paths(&input.0, &input.1, &input.2, &CostModel::default());
+        let tiles: HashSet<[usize; 2]> = paths
+            .iter()
+            .flat_map(|path| path.steps.iter().map(|step| step.position))
+            .collect();
+        assert_eq!(tiles.len(), part2(&input));
+        assert!(paths.iter().all(|path| path.cost == part1(&input)));
+    }
+
+    #[test]
+    fn custom_costs() {
+        let (grid, start, end) = parse(INPUT3);
+        let costs = CostModel {
+            step: 2,
+            turn: 3,
+            ..Default::default()
+        };
+        // 13 steps and 4 turns on the same path as with the default costs
+        assert_eq!(
+            lowest_score(&grid, &start, &end, &costs),
+            Some(2 * 13 + 3 * 4)
+        );
+    }
+
+    #[test]
+    fn u_turn_from_start_heading() {
+        let (grid, start, end) = parse("#####\n#S.E#\n#####");
+        let costs = CostModel {
+            u_turn: true,
+            start_heading: Heading::West,
+            ..Default::default()
+        };
+        assert_eq!(lowest_score(&grid, &start, &end, &costs), Some(2002));
+        let paths = optimal_paths(&grid, &start, &end, &costs);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].turns(), 2);
+        assert_eq!(best_tiles(&grid, &start, &end, &costs), Some(3));
+    }
+
+    #[test]
+    fn start_headings() {
+        let (grid, start, end) = parse("###\n#E#\n#.#\n#S#\n###");
+        let score = |start_heading| {
+            let costs = CostModel {
+                start_heading,
+                ..Default::default()
+            };
+            lowest_score(&grid, &start, &end, &costs)
+        };
+        assert_eq!(score(Heading::North), Some(2));
+        assert_eq!(score(Heading::East), Some(1002));
+        assert_eq!(score(Heading::South), None);
+    }
+
+    #[test]
+    fn unreachable_end() {
+        let (grid, start, end) = parse("#######\n#S.#.E#\n#######");
+        let costs = CostModel::default();
+        assert_eq!(lowest_score(&grid, &start, &end, &costs), None);
+        assert_eq!(lowest_score_astar(&grid, &start, &end, &costs), None);
+        assert!(optimal_paths(&grid, &start, &end, &costs).is_empty());
+        assert_eq!(best_tiles(&grid, &start, &end, &costs), None);
+    }
+
+    #[test]
+    fn part1_astar_examples() {
+        for input in [INPUT1, INPUT2, INPUT3] {
+            assert_eq!(part1_astar(&parse(input)), part1(&parse(input)));
+        }
+    }
+
+    /// Large open maze with a regular pattern of pillars
+    fn large_maze(size: usize) -> String {
+        (0..size)
+            .map(|i| {
+                (0..size)
+                    .map(|j| {
+                        if i == 0 || j == 0 || i == size - 1 || j == size - 1 {
+                            '#'
+                        } else if i == size - 2 && j == 1 {
+                            'S'
+                        } else if i == 1 && j == size - 2 {
+                            'E'
+                        } else if i % 4 == 2 && j % 6 == 3 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn astar_large_maze() {
+        let (grid, start, end) = parse(&large_maze(201));
+        let costs = CostModel::default();
+        assert_eq!(
+            astar(&grid, &start, &end, &costs).score,
+            dijkstra(&grid, &start, &end, &costs).score
+        );
+    }
 }
@@ -1,5 +1,8 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
 
 #[aoc_generator(day17)]
 fn parse(input: &str) -> ([i64; 3], Vec<i64>) {
@@ -8,16 +11,12 @@ fn parse(input: &str) -> ([i64; 3], Vec<i64>) {
     for l in input.lines() {
         if let Some((head, num)) = l.split_once(":") {
             if head == "Register A" {
-                println!("A: {}", num);
                 abc[0] = num.trim().parse().unwrap();
             } else if head == "Register B" {
-                println!("B: {}", num);
                 abc[1] = num.trim().parse().unwrap();
             } else if head == "Register C" {
-                println!("C: {}", num);
                 abc[2] = num.trim().parse().unwrap();
             } else if head == "Program" {
-                println!("Program: {}", num);
                 program = num
                     .trim()
                     .split(',')
@@ -29,17 +28,72 @@ fn parse(input: &str) -> ([i64; 3], Vec<i64>) {
     (abc, program)
 }
 
-struct Computer {
+/// One executed instruction, with the registers before and after it ran.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceStep {
+    pub ip: usize,
+    pub opcode: i64,
+    pub operand: i64,
+    pub before: [i64; 3],
+    pub after: [i64; 3],
+    pub output: Option<i64>,
+}
+
+impl TraceStep {
+    pub const CSV_HEADER: &str =
+        "ip,opcode,operand,a_before,b_before,c_before,a_after,b_after,c_after,output";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.ip,
+            self.opcode,
+            self.operand,
+            self.before[0],
+            self.before[1],
+            self.before[2],
+            self.after[0],
+            self.after[1],
+            self.after[2],
+            self.output.map(|x| x.to_string()).unwrap_or_default()
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"ip\":{},\"opcode\":{},\"operand\":{},\"before\":[{},{},{}],\"after\":[{},{},{}],\"output\":{}}}",
+            self.ip,
+            self.opcode,
+            self.operand,
+            self.before[0],
+            self.before[1],
+            self.before[2],
+            self.after[0],
+            self.after[1],
+            self.after[2],
+            self.output.map(|x| x.to_string()).unwrap_or("null".to_string())
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    Csv,
+    JsonLines,
+}
+
+pub struct Computer {
     a: i64,
     b: i64,
     c: i64,
     ip: usize,
     program: Vec<i64>,
     output: VecDeque<i64>,
+    trace: Option<Vec<TraceStep>>,
 }
 
 impl Computer {
-    fn new(program: Vec<i64>, abc: [i64; 3]) -> Self {
+    pub fn new(program: Vec<i64>, abc: [i64; 3]) -> Self {
         Computer {
             a: abc[0],
             b: abc[1],
@@ -47,7 +101,44 @@ impl Computer {
             ip: 0,
             program,
             output: VecDeque::new(),
+            trace: None,
+        }
+    }
+
+    /// Record every executed instruction while running.
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    pub fn output(&self) -> Vec<i64> {
+        self.output.iter().copied().collect()
+    }
+
+    pub fn trace(&self) -> Option<&[TraceStep]> {
+        self.trace.as_deref()
+    }
+
+    /// Serialise the recorded trace, one instruction per line.
+    pub fn write_trace<W: Write>(&self, writer: &mut W, format: TraceFormat) -> io::Result<()> {
+        let Some(trace) = &self.trace else {
+            return Ok(());
+        };
+        if format == TraceFormat::Csv {
+            writeln!(writer, "{}", TraceStep::CSV_HEADER)?;
+        }
+        for step in trace {
+            match format {
+                TraceFormat::Csv => writeln!(writer, "{}", step.to_csv())?,
+                TraceFormat::JsonLines => writeln!(writer, "{}", step.to_json())?,
+            }
         }
+        Ok(())
+    }
+
+    pub fn save_trace<P: AsRef<Path>>(&self, path: P, format: TraceFormat) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        self.write_trace(&mut file, format)
     }
 
     fn get_combo_value(&self, operand: i64) -> i64 {
@@ -60,10 +151,14 @@ impl Computer {
         }
     }
 
-    fn run(&mut self) {
+    pub fn run(&mut self) {
         while self.ip < self.program.len() {
             let opcode = self.program[self.ip];
             let operand = self.program[self.ip + 1];
+            let ip = self.ip;
+            let mut next_ip = ip + 2;
+            let before = [self.a, self.b, self.c];
+            let out_len = self.output.len();
 
             match opcode {
                 0 => self.a >>= self.get_combo_value(operand),
@@ -71,8 +166,7 @@ impl Computer {
                 2 => self.b = self.get_combo_value(operand) % 8,
                 3 => {
                     if self.a != 0 {
-                        self.ip = operand as usize;
-                        continue;
+                        next_ip = operand as usize;
                     }
                 }
                 4 => self.b ^= self.c,
@@ -81,7 +175,18 @@ impl Computer {
                 7 => self.c = self.a >> self.get_combo_value(operand),
                 _ => panic!("Invalid opcode"),
             }
-            self.ip += 2;
+            if let Some(trace) = &mut self.trace {
+                let step = TraceStep {
+                    ip,
+                    opcode,
+                    operand,
+                    before,
+                    after: [self.a, self.b, self.c],
+                    output: (self.output.len() > out_len).then(|| *self.output.back().unwrap()),
+                };
+                trace.push(step);
+            }
+            self.ip = next_ip;
         }
     }
 }
@@ -125,17 +230,17 @@ fn part2((_abc, program): &([i64; 3], Vec<i64>)) -> i64 {
 mod tests {
     use super::*;
 
-    const INPUT1: &'static str = "Register A: 729\n\
-                                  Register B: 0\n\
-                                  Register C: 0\n\
-                                  \n\
-                                  Program: 0,1,5,4,3,0";
+    const INPUT1: &str = "Register A: 729\n\
+                          Register B: 0\n\
+                          Register C: 0\n\
+                          \n\
+                          Program: 0,1,5,4,3,0";
 
-    const INPUT2: &'static str = "Register A: 2024\n\
-                                 Register B: 0\n\
-                                 Register C: 0\n\
-                                 \n\
-                                 Program: 0,3,5,4,3,0";
+    const INPUT2: &str = "Register A: 2024\n\
+                          Register B: 0\n\
+                          Register C: 0\n\
+                          \n\
+                          Program: 0,3,5,4,3,0";
 
     #[test]
     fn part1_example() {
@@ -146,4 +251,62 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(INPUT2)), 117440);
     }
+
+    #[test]
+    fn trace_example() {
+        let (abc, program) = parse(INPUT1);
+        let mut computer = Computer::new(program, abc).with_trace();
+        computer.run();
+        let trace = computer.trace().unwrap();
+        // 0,1 / 5,4 / 3,0 loops until A reaches 0, which takes 10 iterations
+        assert_eq!(trace.len(), 30);
+        assert_eq!(
+            trace[0],
+            TraceStep {
+                ip: 0,
+                opcode: 0,
+                operand: 1,
+                before: [729, 0, 0],
+                after: [364, 0, 0],
+                output: None,
+            }
+        );
+        let outputs: Vec<i64> = trace.iter().filter_map(|s| s.output).collect();
+        assert_eq!(outputs, computer.output());
+    }
+
+    #[test]
+    fn trace_serialisation() {
+        let (abc, program) = parse(INPUT1);
+        let mut computer = Computer::new(program, abc).with_trace();
+        computer.run();
+
+        let mut csv = Vec::new();
+        computer.write_trace(&mut csv, TraceFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(TraceStep::CSV_HEADER));
+        assert_eq!(lines.next(), Some("0,0,1,729,0,0,364,0,0,"));
+        assert_eq!(lines.next(), Some("2,5,4,364,0,0,364,0,0,4"));
+
+        let mut json = Vec::new();
+        computer
+            .write_trace(&mut json, TraceFormat::JsonLines)
+            .unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert_eq!(
+            json.lines().nth(2),
+            Some(
+                r#"{"ip":4,"opcode":3,"operand":0,"before":[364,0,0],"after":[364,0,0],"output":null}"#
+            )
+        );
+    }
+
+    #[test]
+    fn no_trace_by_default() {
+        let (abc, program) = parse(INPUT1);
+        let mut computer = Computer::new(program, abc);
+        computer.run();
+        assert!(computer.trace().is_none());
+    }
 }
@@ -1,28 +1,35 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
-#[aoc_generator(day18, part1)]
-fn parse1(input: &str) -> HashSet<(i32, i32)> {
-    let n = input.lines().count();
-    // accomadate for test case
-    let mut take_n = 1024;
-    if n < 30 {
-        take_n = 12;
+/// Size of the memory space, and number of bytes fallen when looking for
+/// the shortest path in part 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemorySpace {
+    pub size: i32,
+    pub fallen: usize,
+}
+
+impl MemorySpace {
+    pub const PUZZLE: MemorySpace = MemorySpace {
+        size: 71,
+        fallen: 1024,
+    };
+    pub const EXAMPLE: MemorySpace = MemorySpace {
+        size: 7,
+        fallen: 12,
+    };
+
+    fn target(&self) -> (i32, i32) {
+        (self.size - 1, self.size - 1)
+    }
+
+    fn bounds(&self) -> [i32; 2] {
+        [self.size, self.size]
     }
-    input
-        .lines()
-        .map(|line| {
-            let mut parts = line.split(',');
-            let x = parts.next().unwrap().parse().unwrap();
-            let y = parts.next().unwrap().parse().unwrap();
-            (x, y)
-        })
-        .take(take_n)
-        .collect()
 }
 
-#[aoc_generator(day18, part2)]
-fn parse2(input: &str) -> Vec<(i32, i32)> {
+#[aoc_generator(day18)]
+fn parse(input: &str) -> Vec<(i32, i32)> {
     input
         .lines()
         .map(|line| {
@@ -34,16 +41,31 @@ fn parse2(input: &str) -> Vec<(i32, i32)> {
         .collect()
 }
 
-fn shortest_path(target: (i32, i32), bounds: [i32; 2], bytes_coord: &HashSet<(i32, i32)>) -> i32 {
+/// Shortest path from the origin to `target`, both included,
+/// or `None` if the target cannot be reached (or the origin is corrupted).
+fn shortest_path<F: Fn(&(i32, i32)) -> bool>(
+    target: (i32, i32),
+    bounds: [i32; 2],
+    is_corrupted: F,
+) -> Option<Vec<(i32, i32)>> {
+    if is_corrupted(&(0, 0)) {
+        return None;
+    }
     let mut queue = VecDeque::new();
-    let mut visited = HashSet::new();
-    queue.push_back(((0, 0), 0));
-    visited.insert((0, 0));
-    let mut shortest = 0;
-    while let Some((pos, steps)) = queue.pop_front() {
+    // where we came from to reach each visited position
+    let mut parents: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    queue.push_back((0, 0));
+    parents.insert((0, 0), (0, 0));
+    while let Some(pos) = queue.pop_front() {
         if pos == target {
-            shortest = steps;
-            break;
+            let mut path = vec![pos];
+            let mut curr = pos;
+            while curr != (0, 0) {
+                curr = parents[&curr];
+                path.push(curr);
+            }
+            path.reverse();
+            return Some(path);
         }
 
         for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
@@ -52,81 +74,286 @@ fn shortest_path(target: (i32, i32), bounds: [i32; 2], bytes_coord: &HashSet<(i3
                 && (new_pos.0 < bounds[0])
                 && (new_pos.1 > -1)
                 && (new_pos.1 < bounds[1])
-                && !bytes_coord.contains(&new_pos)
-                && !visited.contains(&new_pos)
+                && !is_corrupted(&new_pos)
+                && !parents.contains_key(&new_pos)
             {
-                queue.push_back((new_pos, steps + 1));
-                visited.insert(new_pos);
+                queue.push_back(new_pos);
+                parents.insert(new_pos, pos);
+            }
+        }
+    }
+    None
+}
+
+/// Shortest path to the exit once `space.fallen` bytes have fallen, if any
+pub fn exit_path(bytes_vec: &[(i32, i32)], space: &MemorySpace) -> Option<Vec<(i32, i32)>> {
+    let bytes_coord: HashSet<(i32, i32)> = bytes_vec.iter().take(space.fallen).copied().collect();
+    shortest_path(space.target(), space.bounds(), |pos| {
+        bytes_coord.contains(pos)
+    })
+}
+
+/// Minimum number of steps to reach the exit once `space.fallen` bytes have fallen
+pub fn min_steps(bytes_vec: &[(i32, i32)], space: &MemorySpace) -> Option<usize> {
+    exit_path(bytes_vec, space).map(|path| path.len() - 1)
+}
+
+/// A byte falls outside of the memory space
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub byte: (i32, i32),
+    pub size: i32,
+}
+
+impl std::fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "byte {:?} falls outside of the {}x{} memory space",
+            self.byte, self.size, self.size
+        )
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+/// Memory space where each cell knows when it gets corrupted,
+/// to answer queries at any time without rebuilding the set of fallen bytes.
+pub struct FallTimeline {
+    size: i32,
+    // number of fallen bytes when each cell gets corrupted, usize::MAX if never
+    corrupted_at: Vec<Vec<usize>>,
+    bytes_vec: Vec<(i32, i32)>,
+}
+
+impl FallTimeline {
+    pub fn new(bytes_vec: &[(i32, i32)], size: i32) -> Result<Self, OutOfBounds> {
+        let mut corrupted_at = vec![vec![usize::MAX; size as usize]; size as usize];
+        for (i, &(x, y)) in bytes_vec.iter().enumerate() {
+            if x < 0 || y < 0 || x >= size || y >= size {
+                return Err(OutOfBounds { byte: (x, y), size });
+            }
+            let time = &mut corrupted_at[x as usize][y as usize];
+            // a byte may fall on an already corrupted cell
+            *time = (*time).min(i + 1);
+        }
+        Ok(FallTimeline {
+            size,
+            corrupted_at,
+            bytes_vec: bytes_vec.to_vec(),
+        })
+    }
+
+    /// Number of fallen bytes when `pos` gets corrupted, if it ever does
+    pub fn corrupted_at(&self, pos: &(i32, i32)) -> Option<usize> {
+        let time = self.corrupted_at[pos.0 as usize][pos.1 as usize];
+        (time != usize::MAX).then_some(time)
+    }
+
+    /// Shortest path to the exit once `fallen` bytes have fallen, if any
+    pub fn shortest_path_at(&self, fallen: usize) -> Option<Vec<(i32, i32)>> {
+        let target = (self.size - 1, self.size - 1);
+        shortest_path(target, [self.size, self.size], |pos| {
+            self.corrupted_at[pos.0 as usize][pos.1 as usize] <= fallen
+        })
+    }
+
+    /// Largest number of fallen bytes for which the exit is still reachable,
+    /// or `None` if it stays reachable until the end.
+    ///
+    /// This is a bottleneck search: the path we look for maximises the
+    /// earliest corruption time among its cells, the origin included.
+    pub fn last_open_time(&self) -> Option<usize> {
+        let target = (self.size - 1, self.size - 1);
+        // best bottleneck found for each cell
+        let mut best = vec![vec![0; self.size as usize]; self.size as usize];
+        let mut heap = BinaryHeap::new();
+        let start_time = self.corrupted_at[0][0];
+        best[0][0] = start_time;
+        heap.push((start_time, (0, 0)));
+        while let Some((bottleneck, pos)) = heap.pop() {
+            if pos == target {
+                return (bottleneck != usize::MAX).then(|| bottleneck - 1);
+            }
+            if bottleneck < best[pos.0 as usize][pos.1 as usize] {
+                continue;
+            }
+            for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+                let new_pos: (i32, i32) = (pos.0 + dx, pos.1 + dy);
+                if (new_pos.0 < 0)
+                    || (new_pos.0 >= self.size)
+                    || (new_pos.1 < 0)
+                    || (new_pos.1 >= self.size)
+                {
+                    continue;
+                }
+                let (i, j) = (new_pos.0 as usize, new_pos.1 as usize);
+                let new_bottleneck = bottleneck.min(self.corrupted_at[i][j]);
+                if new_bottleneck > best[i][j] {
+                    best[i][j] = new_bottleneck;
+                    heap.push((new_bottleneck, new_pos));
+                }
             }
         }
+        unreachable!("the exit is always reachable before any byte falls")
+    }
+
+    /// First byte that makes the exit unreachable, if any
+    pub fn first_blocking_byte(&self) -> Option<(i32, i32)> {
+        self.last_open_time().map(|time| self.bytes_vec[time])
     }
-    shortest
+}
+
+/// First byte that makes the exit unreachable
+pub fn first_blocking_byte(bytes_vec: &[(i32, i32)], space: &MemorySpace) -> (i32, i32) {
+    FallTimeline::new(bytes_vec, space.size)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .first_blocking_byte()
+        .expect("No byte found that stopped us from reaching the target")
 }
 
 #[aoc(day18, part1)]
-fn part1(bytes_coord: &HashSet<(i32, i32)>) -> i32 {
-    let max = bytes_coord.iter().max_by(|x, y| x.0.cmp(&y.0)).unwrap().0;
-    let target = (max, max);
-    let bounds = [max + 1, max + 1];
-    shortest_path(target, bounds, bytes_coord)
+fn part1(bytes_vec: &[(i32, i32)]) -> String {
+    match min_steps(bytes_vec, &MemorySpace::PUZZLE) {
+        Some(steps) => steps.to_string(),
+        None => "no path to the exit".to_string(),
+    }
 }
 
 #[aoc(day18, part2)]
 fn part2(bytes_vec: &[(i32, i32)]) -> String {
-    let mut target = (70, 70);
-    let mut start = 1025;
-    if bytes_vec.len() < 30 {
-        target = (6, 6);
-        start = 13;
-    }
-    let bounds = [target.0 + 1, target.1 + 1];
-    for i in start..bytes_vec.len() {
-        let bytes_coord: HashSet<(i32, i32)> = bytes_vec.iter().take(i).copied().collect();
-        let shortest = shortest_path(target, bounds, &bytes_coord);
-        if shortest == 0 {
-            return format!("{:?}", bytes_vec[i - 1]);
-        }
-    }
-    panic!("No byte found that stopped us from reaching the target");
+    format!("{:?}", first_blocking_byte(bytes_vec, &MemorySpace::PUZZLE))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "5,4\n\
-                                 4,2\n\
-                                 4,5\n\
-                                 3,0\n\
-                                 2,1\n\
-                                 6,3\n\
-                                 2,4\n\
-                                 1,5\n\
-                                 0,6\n\
-                                 3,3\n\
-                                 2,6\n\
-                                 5,1\n\
-                                 1,2\n\
-                                 5,5\n\
-                                 2,5\n\
-                                 6,5\n\
-                                 1,4\n\
-                                 0,4\n\
-                                 6,4\n\
-                                 1,1\n\
-                                 6,1\n\
-                                 1,0\n\
-                                 0,5\n\
-                                 1,6\n\
-                                 2,0";
+    const INPUT: &str = "5,4\n\
+                         4,2\n\
+                         4,5\n\
+                         3,0\n\
+                         2,1\n\
+                         6,3\n\
+                         2,4\n\
+                         1,5\n\
+                         0,6\n\
+                         3,3\n\
+                         2,6\n\
+                         5,1\n\
+                         1,2\n\
+                         5,5\n\
+                         2,5\n\
+                         6,5\n\
+                         1,4\n\
+                         0,4\n\
+                         6,4\n\
+                         1,1\n\
+                         6,1\n\
+                         1,0\n\
+                         0,5\n\
+                         1,6\n\
+                         2,0";
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse1(INPUT)), 22);
+        assert_eq!(min_steps(&parse(INPUT), &MemorySpace::EXAMPLE), Some(22));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse2(INPUT)), "(6, 1)");
+        assert_eq!(
+            first_blocking_byte(&parse(INPUT), &MemorySpace::EXAMPLE),
+            (6, 1)
+        );
+    }
+
+    #[test]
+    fn fallen_bytes_count() {
+        let bytes_vec = parse(INPUT);
+        let space = |fallen| MemorySpace { size: 7, fallen };
+        // nothing fell yet: straight to the corner
+        assert_eq!(min_steps(&bytes_vec, &space(0)), Some(12));
+        assert_eq!(min_steps(&bytes_vec, &space(20)), Some(24));
+        // the 21st byte cuts the last way through
+        assert_eq!(min_steps(&bytes_vec, &space(21)), None);
+    }
+
+    #[test]
+    fn exit_path_example() {
+        let bytes_vec = parse(INPUT);
+        let path = exit_path(&bytes_vec, &MemorySpace::EXAMPLE).unwrap();
+        assert_eq!(path.len(), 23);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(6, 6)));
+        let fallen: HashSet<&(i32, i32)> = bytes_vec.iter().take(12).collect();
+        for w in path.windows(2) {
+            assert_eq!((w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs(), 1);
+            assert!(!fallen.contains(&w[1]));
+        }
+    }
+
+    #[test]
+    fn exit_at_origin() {
+        let space = MemorySpace { size: 1, fallen: 0 };
+        assert_eq!(exit_path(&[], &space), Some(vec![(0, 0)]));
+        assert_eq!(min_steps(&[], &space), Some(0));
+    }
+
+    #[test]
+    fn timeline_queries() {
+        let bytes_vec = parse(INPUT);
+        let timeline = FallTimeline::new(&bytes_vec, 7).unwrap();
+        assert_eq!(timeline.corrupted_at(&(5, 4)), Some(1));
+        assert_eq!(timeline.corrupted_at(&(6, 1)), Some(21));
+        assert_eq!(timeline.corrupted_at(&(0, 0)), None);
+        for fallen in 0..=bytes_vec.len() {
+            let space = MemorySpace { size: 7, fallen };
+            assert_eq!(
+                timeline.shortest_path_at(fallen),
+                exit_path(&bytes_vec, &space)
+            );
+        }
+        assert_eq!(timeline.last_open_time(), Some(20));
+        assert_eq!(timeline.first_blocking_byte(), Some((6, 1)));
+    }
+
+    #[test]
+    fn timeline_never_blocked() {
+        let timeline = FallTimeline::new(&[(1, 0), (1, 1)], 3).unwrap();
+        assert_eq!(timeline.last_open_time(), None);
+        assert_eq!(timeline.first_blocking_byte(), None);
+        assert_eq!(timeline.shortest_path_at(2).map(|p| p.len()), Some(5));
+    }
+
+    #[test]
+    fn byte_on_origin() {
+        let bytes_vec = [(0, 0), (1, 0), (1, 1), (0, 1), (0, 0), (1, 1)];
+        let timeline = FallTimeline::new(&bytes_vec, 2).unwrap();
+        for fallen in 0..=bytes_vec.len() {
+            let space = MemorySpace { size: 2, fallen };
+            assert_eq!(
+                timeline.shortest_path_at(fallen),
+                exit_path(&bytes_vec, &space)
+            );
+        }
+        assert_eq!(
+            min_steps(&bytes_vec, &MemorySpace { size: 2, fallen: 1 }),
+            None
+        );
+        assert_eq!(timeline.last_open_time(), Some(0));
+        assert_eq!(timeline.first_blocking_byte(), Some((0, 0)));
+    }
+
+    #[test]
+    fn byte_out_of_bounds() {
+        assert_eq!(
+            FallTimeline::new(&[(1, 0), (3, 1)], 3).err(),
+            Some(OutOfBounds {
+                byte: (3, 1),
+                size: 3
+            })
+        );
+        assert!(FallTimeline::new(&[(0, -1)], 3).is_err());
     }
 }
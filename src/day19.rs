@@ -1,120 +1,518 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use num::traits::CheckedAdd;
+use num::{One, Zero};
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 
-fn char_to_digit(c: char) -> usize {
-    match c {
-        'w' => 0,
-        'u' => 1,
-        'b' => 2,
-        'r' => 3,
-        'g' => 4,
-        _ => 5,
+/// The number of arrangements does not fit in the integer type used to count them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CountOverflow;
+
+impl std::fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "number of arrangements overflows, use the u128 version")
+    }
+}
+
+impl std::error::Error for CountOverflow {}
+
+/// Patterns and designs, each colour being replaced by its index in `alphabet`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Towels {
+    pub patterns: HashSet<Vec<usize>>,
+    pub designs: Vec<Vec<usize>>,
+    pub alphabet: Vec<char>,
+}
+
+impl Towels {
+    /// Colours of a pattern or design, as written in the input
+    pub fn colours(&self, stripes: &[usize]) -> String {
+        stripes.iter().map(|c| self.alphabet[*c]).collect()
+    }
+
+    /// Write an arrangement the way patterns are listed in the input
+    pub fn format_arrangement(&self, arrangement: &[&[usize]]) -> String {
+        arrangement
+            .iter()
+            .map(|pattern| self.colours(pattern))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A design uses a colour that no pattern has, so it cannot be made
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownColour {
+    pub colour: char,
+    pub design: String,
+}
+
+impl std::fmt::Display for UnknownColour {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "colour '{}' of design {} is not in any pattern",
+            self.colour, self.design
+        )
     }
 }
 
+impl std::error::Error for UnknownColour {}
+
 #[aoc_generator(day19)]
-fn parse(input: &str) -> (HashSet<Vec<usize>>, Vec<Vec<usize>>) {
+pub fn parse(input: &str) -> Result<Towels, UnknownColour> {
     let mut parts = input.split("\n\n");
+    // colours in order of first appearance in the patterns, and their index
+    let mut alphabet: Vec<char> = Vec::new();
+    let mut colour_idx: HashMap<char, usize> = HashMap::new();
     let patterns: HashSet<Vec<usize>> = parts
         .next()
         .unwrap()
         .split(", ")
-        .map(|pat| pat.chars().map(char_to_digit).collect())
+        .map(|pat| {
+            pat.trim()
+                .chars()
+                .map(|c| {
+                    *colour_idx.entry(c).or_insert_with(|| {
+                        alphabet.push(c);
+                        alphabet.len() - 1
+                    })
+                })
+                .collect()
+        })
         .collect();
     let designs: Vec<Vec<usize>> = parts
         .next()
         .unwrap()
         .lines()
-        .map(|des| des.chars().map(char_to_digit).collect())
-        .collect();
-    (patterns, designs)
+        .map(|des| {
+            des.chars()
+                .map(|c| {
+                    colour_idx.get(&c).copied().ok_or_else(|| UnknownColour {
+                        colour: c,
+                        design: des.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Towels {
+        patterns,
+        designs,
+        alphabet,
+    })
+}
+
+/// End of the design the counts of `Trie::ways_table` start from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CountFrom {
+    Start,
+    End,
+}
+
+/// Trie of the towel patterns, to find all patterns matching at a given
+/// position of a design in a single walk.
+pub struct Trie {
+    alphabet_size: usize,
+    // `children[node * alphabet_size + colour]` is the child node, 0 if none
+    children: Vec<usize>,
+    // whether a pattern ends at each node
+    terminal: Vec<bool>,
 }
 
-fn can_make(
-    design: &[usize],
-    patterns: &HashSet<Vec<usize>>,
-    cache: &mut HashMap<Vec<usize>, bool>,
-) -> bool {
-    if design.is_empty() {
-        return true;
+impl Trie {
+    pub fn new(patterns: &HashSet<Vec<usize>>) -> Self {
+        let alphabet_size = patterns.iter().flatten().max().map_or(0, |c| c + 1);
+        let mut trie = Trie {
+            alphabet_size,
+            children: vec![0; alphabet_size],
+            terminal: vec![false],
+        };
+        for pattern in patterns.iter() {
+            let mut node = 0;
+            for colour in pattern.iter() {
+                let idx = node * alphabet_size + colour;
+                if trie.children[idx] == 0 {
+                    trie.children[idx] = trie.terminal.len();
+                    trie.children.extend(std::iter::repeat_n(0, alphabet_size));
+                    trie.terminal.push(false);
+                }
+                node = trie.children[idx];
+            }
+            trie.terminal[node] = true;
+        }
+        trie
     }
-    if let Some(&result) = cache.get(design) {
-        return result;
+
+    /// Lengths of all the patterns `design` starts with
+    fn prefix_lengths<'a>(&'a self, design: &'a [usize]) -> impl Iterator<Item = usize> + 'a {
+        design
+            .iter()
+            .scan(0, |node, colour| {
+                *node = self.children[*node * self.alphabet_size + colour];
+                (*node != 0).then_some(*node)
+            })
+            .enumerate()
+            .filter(|(_, node)| self.terminal[*node])
+            .map(|(i, _)| i + 1)
     }
 
-    let result = patterns
-        .iter()
-        .filter(|&pattern| design.starts_with(pattern))
-        .any(|pattern| can_make(&design[pattern.len()..], patterns, cache));
+    /// Number of colours of `design` matched by the start of some pattern
+    fn match_depth(&self, design: &[usize]) -> usize {
+        let mut node = 0;
+        design
+            .iter()
+            .take_while(|colour| {
+                node = self.children[node * self.alphabet_size + *colour];
+                node != 0
+            })
+            .count()
+    }
 
-    cache.insert(design.to_vec(), result);
-    result
-}
+    /// Counting engine behind every query on a design. From the end,
+    /// `table[i]` is the number of arrangements for `design[i..]`, from the
+    /// start for `design[..i]`. Counts are summed with `add`: a checked
+    /// addition gives exact counts (`None` on overflow), a saturating one is
+    /// enough when only feasibility matters, that is whether a count is not zero.
+    fn ways_table<T, F>(&self, design: &[usize], from: CountFrom, add: F) -> Option<Vec<T>>
+    where
+        T: Copy + Zero + One,
+        F: Fn(&T, &T) -> Option<T>,
+    {
+        let n = design.len();
+        let mut table = vec![T::zero(); n + 1];
+        table[if from == CountFrom::Start { 0 } else { n }] = T::one();
+        for k in 0..n {
+            // positions are visited so that a count is complete before being used
+            let i = if from == CountFrom::Start {
+                k
+            } else {
+                n - 1 - k
+            };
+            for len in self.prefix_lengths(&design[i..]) {
+                let (src, dst) = match from {
+                    CountFrom::Start => (i, i + len),
+                    CountFrom::End => (i + len, i),
+                };
+                table[dst] = add(&table[dst], &table[src])?;
+            }
+        }
+        Some(table)
+    }
 
-#[aoc(day19, part1)]
-fn part1((patterns, designs): &(HashSet<Vec<usize>>, Vec<Vec<usize>>)) -> usize {
-    let mut cache: HashMap<Vec<usize>, bool> = HashMap::new();
-    let mut count = 0;
+    /// Whether each entry of the table counting from `from` is not zero
+    fn feasible(&self, design: &[usize], from: CountFrom) -> Vec<bool> {
+        self.ways_table(design, from, |a: &u8, b| Some(a.saturating_add(*b)))
+            .unwrap()
+            .iter()
+            .map(|ways| *ways > 0)
+            .collect()
+    }
+
+    /// Whether `design` can be made with the patterns
+    pub fn can_make(&self, design: &[usize]) -> bool {
+        self.feasible(design, CountFrom::End)[0]
+    }
 
-    for design in designs {
-        if can_make(design, patterns, &mut cache) {
-            count += 1;
+    /// Number of ways to build `design`, `None` if it overflows `T`
+    pub fn count_ways<T: Copy + Zero + One + CheckedAdd>(&self, design: &[usize]) -> Option<T> {
+        self.ways_table(design, CountFrom::End, T::checked_add)
+            .map(|ways| ways[0])
+    }
+
+    /// Every way to make `design`, each given as the patterns used, in order.
+    /// Arrangements are only built when iterating, so `take` can limit the work.
+    pub fn arrangements<'a>(&'a self, design: &'a [usize]) -> Arrangements<'a> {
+        // `can_finish[i]` tells whether `design[i..]` can be made
+        let can_finish = self.feasible(design, CountFrom::End);
+        let stack = if can_finish[0] {
+            vec![Vec::new()]
+        } else {
+            Vec::new()
+        };
+        Arrangements {
+            trie: self,
+            design,
+            can_finish,
+            stack,
         }
     }
-    count
-}
 
-fn count_ways(design: &[usize], patterns: &HashSet<Vec<usize>>, cache: &mut HashMap<Vec<usize>, usize>) -> usize {
-    if design.is_empty() {
-        return 1;
+    /// Explain why `design` cannot be made, or `None` if it can.
+    pub fn explain_failure(&self, design: &[usize]) -> Option<Failure> {
+        // `reachable[i]` tells whether `design[..i]` can be made
+        let reachable = self.feasible(design, CountFrom::Start);
+        if reachable[design.len()] {
+            return None;
+        }
+        let buildable_prefix = reachable.iter().rposition(|r| *r).unwrap();
+        // a pattern starting before the end of the prefix may go further
+        let failed_at = (0..=buildable_prefix)
+            .filter(|i| reachable[*i])
+            .map(|i| i + self.match_depth(&design[i..]))
+            .max()
+            .unwrap();
+        Some(Failure {
+            buildable_prefix,
+            failed_at,
+        })
     }
-    if let Some(&count) = cache.get(design) {
-        return count;
+}
+
+/// Why a design cannot be made
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// Length of the longest prefix of the design that can be made
+    pub buildable_prefix: usize,
+    /// Position of the first colour that no pattern can match, whichever
+    /// buildable prefix it starts after
+    pub failed_at: usize,
+}
+
+/// Iterator over the arrangements of a design, see `Trie::arrangements`
+pub struct Arrangements<'a> {
+    trie: &'a Trie,
+    design: &'a [usize],
+    can_finish: Vec<bool>,
+    // partial arrangements still to extend
+    stack: Vec<Vec<usize>>,
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<&'a [usize]>;
+
+    fn next(&mut self) -> Option<Vec<&'a [usize]>> {
+        while let Some(lengths) = self.stack.pop() {
+            let pos: usize = lengths.iter().sum();
+            if pos == self.design.len() {
+                let mut start = 0;
+                let patterns = lengths
+                    .iter()
+                    .map(|len| {
+                        start += len;
+                        &self.design[start - len..start]
+                    })
+                    .collect();
+                return Some(patterns);
+            }
+            let nexts: Vec<usize> = self
+                .trie
+                .prefix_lengths(&self.design[pos..])
+                .filter(|len| self.can_finish[pos + len])
+                .collect();
+            // reversed so that shorter patterns come out first
+            for len in nexts.into_iter().rev() {
+                let mut next = lengths.clone();
+                next.push(len);
+                self.stack.push(next);
+            }
+        }
+        None
     }
+}
 
-    let count = patterns.iter()
-        .filter(|&pattern| design.starts_with(pattern))
-        .map(|pattern| count_ways(&design[pattern.len()..], patterns, cache))
-        .sum();
+#[aoc(day19, part1)]
+pub fn part1(towels: &Towels) -> usize {
+    let trie = Trie::new(&towels.patterns);
+    towels
+        .designs
+        .par_iter()
+        .filter(|design| trie.can_make(design))
+        .count()
+}
 
-    cache.insert(design.to_vec(), count);
-    count
+/// Total number of arrangements of all the designs, counted with `T`
+fn total_ways<T>(towels: &Towels) -> Result<T, CountOverflow>
+where
+    T: Copy + Zero + One + CheckedAdd + Send + Sync,
+{
+    let trie = Trie::new(&towels.patterns);
+    towels
+        .designs
+        .par_iter()
+        .map(|design| trie.count_ways::<T>(design))
+        .try_reduce(T::zero, |a, b| a.checked_add(&b))
+        .ok_or(CountOverflow)
 }
 
 #[aoc(day19, part2)]
-fn part2((patterns, designs): &(HashSet<Vec<usize>>, Vec<Vec<usize>>)) -> usize {
-    let mut cache: HashMap<Vec<usize>, usize> = HashMap::new();
-    let mut count = 0;
+pub fn part2(towels: &Towels) -> Result<usize, CountOverflow> {
+    total_ways(towels)
+}
 
-    for design in designs {
-        count += count_ways(design, patterns, &mut cache);
-    }
-    count
+/// Same as `part2`, for inputs where counts overflow `usize`
+#[aoc(day19, part2, u128)]
+pub fn part2_u128(towels: &Towels) -> Result<u128, CountOverflow> {
+    total_ways(towels)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "r, wr, b, g, bwu, rb, gb, br\n\
-                                 \n\
-                                 brwrr\n\
-                                 bggr\n\
-                                 gbbr\n\
-                                 rrbgbr\n\
-                                 ubwu\n\
-                                 bwurrg\n\
-                                 brgr\n\
-                                 bbrgwb";
+    const INPUT: &str = "r, wr, b, g, bwu, rb, gb, br\n\
+                         \n\
+                         brwrr\n\
+                         bggr\n\
+                         gbbr\n\
+                         rrbgbr\n\
+                         ubwu\n\
+                         bwurrg\n\
+                         brgr\n\
+                         bbrgwb";
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse(INPUT)), 6);
+        assert_eq!(part1(&parse(INPUT).unwrap()), 6);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(INPUT)), 16);
+        assert_eq!(part2(&parse(INPUT).unwrap()), Ok(16));
+    }
+
+    #[test]
+    fn part2_u128_example() {
+        assert_eq!(part2_u128(&parse(INPUT).unwrap()), Ok(16));
+    }
+
+    #[test]
+    fn large_counts() {
+        // ways to split 150 "a" into "a" and "aa" follow the Fibonacci sequence
+        let input = format!("a, aa\n\n{}", "a".repeat(150));
+        let input = parse(&input).unwrap();
+        let trie = Trie::new(&input.patterns);
+        assert_eq!(
+            trie.count_ways::<u128>(&input.designs[0]),
+            Some(fibonacci(151))
+        );
+        // usize overflows but the design is still seen as possible
+        assert_eq!(trie.count_ways::<usize>(&input.designs[0]), None);
+        assert_eq!(part1(&input), 1);
+        assert_eq!(part2(&input), Err(CountOverflow));
+        assert_eq!(part2_u128(&input), Ok(fibonacci(151)));
+    }
+
+    #[test]
+    fn overflowing_sums() {
+        // each design overflows usize
+        let input = format!("a, aa\n\n{0}\n{0}", "a".repeat(100));
+        let input = parse(&input).unwrap();
+        assert_eq!(part2(&input), Err(CountOverflow));
+        assert_eq!(part2_u128(&input), Ok(2 * fibonacci(101)));
+        // each design fits in usize, but not their sum
+        let input = format!("a, aa\n\n{0}\n{0}", "a".repeat(92));
+        let input = parse(&input).unwrap();
+        let trie = Trie::new(&input.patterns);
+        assert_eq!(
+            trie.count_ways::<usize>(&input.designs[0]),
+            Some(fibonacci(93) as usize)
+        );
+        assert_eq!(part2(&input), Err(CountOverflow));
+        assert_eq!(part2_u128(&input), Ok(2 * fibonacci(93)));
+    }
+
+    fn fibonacci(n: usize) -> u128 {
+        (0..n).fold((0, 1), |(a, b), _| (b, a + b)).0
     }
-}
 
+    #[test]
+    fn trie_prefix_lengths() {
+        let towels = parse(INPUT).unwrap();
+        let (trie, designs) = (Trie::new(&towels.patterns), &towels.designs);
+        // bwurrg
+        let design = &designs[5];
+        assert_eq!(trie.prefix_lengths(design).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(trie.count_ways::<usize>(design), Some(1));
+    }
+
+    #[test]
+    fn count_from_both_ends() {
+        let towels = parse(INPUT).unwrap();
+        let trie = Trie::new(&towels.patterns);
+        for design in towels.designs.iter() {
+            let from_start =
+                trie.ways_table::<usize, _>(design, CountFrom::Start, CheckedAdd::checked_add);
+            let from_end =
+                trie.ways_table::<usize, _>(design, CountFrom::End, CheckedAdd::checked_add);
+            assert_eq!(from_start.unwrap()[design.len()], from_end.unwrap()[0]);
+        }
+    }
+
+    #[test]
+    fn other_colours() {
+        // 'x' and 'y' used to be mapped to the same colour
+        let input = parse("xa, ya, a\n\nxaya\nyyaa\naa").unwrap();
+        assert_eq!(part1(&input), 2);
+        assert_eq!(part2(&input), Ok(2));
+        assert_eq!(part2_u128(&input), Ok(2));
+    }
+
+    #[test]
+    fn unknown_colour() {
+        assert_eq!(
+            parse("r, wr\n\nwrr\nwbr"),
+            Err(UnknownColour {
+                colour: 'b',
+                design: "wbr".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn list_arrangements() {
+        let towels = parse(INPUT).unwrap();
+        let (trie, designs) = (Trie::new(&towels.patterns), &towels.designs);
+        let arrangements: Vec<String> = trie
+            .arrangements(&designs[3])
+            .map(|arrangement| towels.format_arrangement(&arrangement))
+            .collect();
+        assert_eq!(
+            arrangements,
+            vec![
+                "r, r, b, g, b, r",
+                "r, r, b, g, br",
+                "r, r, b, gb, r",
+                "r, rb, g, b, r",
+                "r, rb, g, br",
+                "r, rb, gb, r"
+            ]
+        );
+        assert_eq!(towels.colours(&designs[3]), "rrbgbr");
+        assert_eq!(trie.arrangements(&designs[3]).take(2).count(), 2);
+        assert_eq!(trie.arrangements(&designs[4]).next(), None);
+    }
+
+    #[test]
+    fn explain_failures() {
+        let towels = parse(INPUT).unwrap();
+        let (trie, designs) = (Trie::new(&towels.patterns), &towels.designs);
+        assert_eq!(trie.explain_failure(&designs[0]), None);
+        // ubwu: no pattern starts with u
+        assert_eq!(
+            trie.explain_failure(&designs[4]),
+            Some(Failure {
+                buildable_prefix: 0,
+                failed_at: 0
+            })
+        );
+        // bbrgwb: "bbrg" can be made, but "wb" only matches the start of "wr"
+        assert_eq!(
+            trie.explain_failure(&designs[7]),
+            Some(Failure {
+                buildable_prefix: 4,
+                failed_at: 5
+            })
+        );
+        // "ab" then "c" make the prefix, but "abcdx" goes further
+        let towels = parse("ab, abcdx, c\n\nabcdc").unwrap();
+        let trie = Trie::new(&towels.patterns);
+        assert_eq!(
+            trie.explain_failure(&towels.designs[0]),
+            Some(Failure {
+                buildable_prefix: 3,
+                failed_at: 4
+            })
+        );
+    }
+}
@@ -78,12 +78,12 @@ pub fn part2(table: &[Vec<usize>]) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "7 6 4 2 1\n\
-                                 1 2 7 8 9\n\
-                                 9 7 6 2 1\n\
-                                 1 3 2 4 5\n\
-                                 8 6 4 4 1\n\
-                                 1 3 6 7 9";
+    const INPUT: &str = "7 6 4 2 1\n\
+                         1 2 7 8 9\n\
+                         9 7 6 2 1\n\
+                         1 3 2 4 5\n\
+                         8 6 4 4 1\n\
+                         1 3 6 7 9";
 
     #[test]
     fn part1_example() {
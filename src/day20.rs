@@ -1,12 +1,18 @@
+use crate::grid::grid_to_string;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashMap;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
 
 const DIRECTIONS: [(i32, i32); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
 
 type Grid = Vec<Vec<char>>;
-type PathMap = HashMap<(i32, i32), i32>;
+/// Distance to each cell, row by row, `UNREACHED` for walls and cells
+/// that cannot be reached
+type Distances = Vec<i32>;
 
-struct RaceInfo {
+const UNREACHED: i32 = -1;
+
+pub struct RaceInfo {
     start: Pos,
     end: Pos,
     dim: (i32, i32),
@@ -19,7 +25,7 @@ struct Pos {
 }
 
 #[aoc_generator(day20)]
-fn parse(input: &str) -> (Grid, RaceInfo) {
+pub fn parse(input: &str) -> (Grid, RaceInfo) {
     let n_rows = input.lines().count();
     let n_cols = input.lines().next().unwrap().chars().count();
     let mut start = Pos { x: 0, y: 0 };
@@ -50,58 +56,162 @@ fn parse(input: &str) -> (Grid, RaceInfo) {
 #[aoc(day20, part1)]
 fn part1((grid, race_info): &(Grid, RaceInfo)) -> usize {
     solve(grid, race_info, 100, 2)
+        .expect("end of the race is unreachable")
+        .0
 }
 
 #[aoc(day20, part2)]
 fn part2((grid, race_info): &(Grid, RaceInfo)) -> usize {
     solve(grid, race_info, 100, 20)
+        .expect("end of the race is unreachable")
+        .0
+}
+
+/// A way to cheat: leave the track at `start` and come back to it at `end`
+/// after `duration` picoseconds, saving `saving` picoseconds.
+/// Coordinates are (row, column).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cheat {
+    pub start: (i32, i32),
+    pub end: (i32, i32),
+    pub duration: i32,
+    pub saving: i32,
 }
 
-fn solve(grid: &Grid, race_info: &RaceInfo, min_save: i32, max_iter: i32) -> usize {
-    let (h, w) = race_info.dim;
-    let (start, end) = (race_info.start.clone(), race_info.end.clone());
+/// Every cheat lasting at most `max_iter` picoseconds that saves some time,
+/// sorted by start then end position.
+/// Returns `None` if the end cannot be reached from the start.
+pub fn cheats(grid: &Grid, race_info: &RaceInfo, max_iter: i32) -> Option<Vec<Cheat>> {
+    let (from_start, from_end, best) = race_distances(grid, race_info)?;
+    let mut cheats: Vec<Cheat> = (0..from_start.len())
+        .into_par_iter()
+        .filter(|idx| from_start[*idx] != UNREACHED)
+        .flat_map_iter(|idx| {
+            find_cheats(
+                idx,
+                best - from_start[idx],
+                &from_end,
+                race_info.dim,
+                max_iter,
+            )
+        })
+        .collect();
+    cheats.sort();
+    Some(cheats)
+}
 
-    let mut path_map: PathMap = HashMap::new();
-    path_map.insert((end.x, end.y), 0);
-    let mut dist = 0;
-    let mut curr_pos = end;
-    let mut curr_dir = (0, 0);
-    loop {
+/// Number of cheats saving at least `min_save`, and how many cheats save
+/// each amount of time (all positive savings, not only those above `min_save`).
+/// Returns `None` if the end cannot be reached from the start.
+pub fn solve(
+    grid: &Grid,
+    race_info: &RaceInfo,
+    min_save: i32,
+    max_iter: i32,
+) -> Option<(usize, BTreeMap<i32, usize>)> {
+    let (from_start, from_end, best) = race_distances(grid, race_info)?;
+    // a cheat always saves less than the whole race
+    let n_savings = best as usize;
+    let counts = (0..from_start.len())
+        .into_par_iter()
+        .filter(|idx| from_start[*idx] != UNREACHED)
+        .fold(
+            || vec![0; n_savings],
+            |mut counts, idx| {
+                let remaining = best - from_start[idx];
+                for cheat in find_cheats(idx, remaining, &from_end, race_info.dim, max_iter) {
+                    counts[cheat.saving as usize] += 1;
+                }
+                counts
+            },
+        )
+        .reduce(
+            || vec![0; n_savings],
+            |mut counts, other| {
+                counts.iter_mut().zip(other).for_each(|(n, m)| *n += m);
+                counts
+            },
+        );
+
+    let histogram: BTreeMap<i32, usize> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| **n > 0)
+        .map(|(saving, n)| (saving as i32, *n))
+        .collect();
+    let count = histogram.range(min_save..).map(|(_, n)| n).sum();
+    Some((count, histogram))
+}
+
+/// Distances from the start and from the end, and the time of the race
+/// without cheating, or `None` if the end cannot be reached.
+fn race_distances(grid: &Grid, race_info: &RaceInfo) -> Option<(Distances, Distances, i32)> {
+    let from_start = distances(&race_info.start, grid, race_info.dim);
+    let from_end = distances(&race_info.end, grid, race_info.dim);
+    let best = from_start[index(&race_info.end, race_info.dim)];
+    (best != UNREACHED).then_some((from_start, from_end, best))
+}
+
+/// Distance of every track position reachable from `from`
+fn distances(from: &Pos, grid: &Grid, dim: (i32, i32)) -> Distances {
+    let mut dist: Distances = vec![UNREACHED; (dim.0 * dim.1) as usize];
+    dist[index(from, dim)] = 0;
+    let mut queue = VecDeque::from([(from.clone(), 0)]);
+    while let Some((curr_pos, d)) = queue.pop_front() {
         for (dx, dy) in DIRECTIONS.iter() {
             let next_pos = Pos {
                 x: curr_pos.x + dx,
                 y: curr_pos.y + dy,
             };
-            // stay inside the grid, dont run into a wall & dont go backwards
-            if bound_check(&next_pos, (h, w))
+            // stay inside the grid, dont run into a wall & dont come back
+            if bound_check(&next_pos, dim)
                 && !is_wall(&next_pos, grid)
-                && get_opposite(dx, dy) != curr_dir
+                && dist[index(&next_pos, dim)] == UNREACHED
             {
-                dist += 1;
-                path_map.insert((next_pos.x, next_pos.y), dist);
-                curr_pos = next_pos;
-                curr_dir = (*dx, *dy);
-                break;
+                dist[index(&next_pos, dim)] = d + 1;
+                queue.push_back((next_pos, d + 1));
             }
         }
-        if curr_pos == start {
-            break;
-        }
     }
+    dist
+}
 
-    let mut savings: Vec<i32> = Vec::new();
+fn index(p: &Pos, (_, w): (i32, i32)) -> usize {
+    (p.x * w + p.y) as usize
+}
+
+/// Describe the histogram the way the puzzle does, one line per saving
+/// of at least `min_save`.
+pub fn format_savings(histogram: &BTreeMap<i32, usize>, min_save: i32) -> String {
+    histogram
+        .range(min_save..)
+        .map(|(saving, n)| {
+            if *n == 1 {
+                format!("There is one cheat that saves {} picoseconds.", saving)
+            } else {
+                format!("There are {} cheats that save {} picoseconds.", n, saving)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    for ((x, y), dist1) in path_map.iter() {
-        let pos1 = Pos { x: *x, y: *y };
-        for cheat_duration in 2..(max_iter + 1) {
-            find_cheat(&pos1, dist1, &path_map, &mut savings, cheat_duration);
+/// Draw `cheat` on the grid the way the puzzle does: cells of the cheat are
+/// numbered in order, moving along the rows first, then along the columns.
+pub fn render_cheat(grid: &Grid, cheat: &Cheat) -> String {
+    let mut grid = grid.clone();
+    let (mut x, mut y) = cheat.start;
+    let mut step = 0;
+    while (x, y) != cheat.end {
+        if x != cheat.end.0 {
+            x += (cheat.end.0 - x).signum();
+        } else {
+            y += (cheat.end.1 - y).signum();
         }
+        step += 1;
+        grid[x as usize][y as usize] = char::from_digit(step % 10, 10).unwrap();
     }
-
-    savings
-        .iter()
-        .filter(|v| *v > &(min_save - 1))
-        .count()
+    grid_to_string(&grid)
 }
 
 fn bound_check(p: &Pos, (h, w): (i32, i32)) -> bool {
@@ -112,82 +222,202 @@ fn is_wall(p: &Pos, grid: &Grid) -> bool {
     grid[p.x as usize][p.y as usize] == '#'
 }
 
-fn get_opposite(dx: &i32, dy: &i32) -> (i32, i32) {
-    let mut opp = (0, 0);
-    if dx != &0 {
-        opp.0 = -dx;
-    } else if dy != &0 {
-        opp.1 = -dy;
-    }
-    opp
-}
-
-fn find_cheat(
-    pos1: &Pos,
-    dist1: &i32,
-    path_map: &PathMap,
-    savings: &mut Vec<i32>,
-    cheat_duration: i32,
-) {
-    // Find all positions that are at `cheat_duration` distance
-    for x in 0..(cheat_duration + 1) {
-        let y = cheat_duration - x;
-        for (i, (xm, ym)) in [(1, 1), (-1, 1), (1, -1), (-1, -1)].iter().enumerate() {
-            // when x or y is 0 we can skip some redundant (xm, ym)
-            if ((x == 0) && ((i == 1) || (i == 3))) || ((y == 0) && ((i == 2) || (i == 3))) {
-                continue;
-            }
-            let pos2 = Pos {
-                x: pos1.x + (x * xm),
-                y: pos1.y + (y * ym),
-            };
-            // does this point to a position in path_map ?
-            if let Some(dist2) = path_map.get(&(pos2.x, pos2.y)) {
-                // only test one way
-                if dist1 < dist2 {
-                    continue;
-                }
-                // is it a potential shortcut ?
-                // ie, L1 distance should be smaller than (dist1 - dist2 - cheat_duration)
-                let diff = dist1 - dist2 - cheat_duration;
-                let l1_dist = pos1.x.abs_diff(pos2.x) + pos1.y.abs_diff(pos2.y);
-                if (l1_dist as i32) < diff {
-                    savings.push(diff);
-                }
-            }
-        }
-    }
+/// Cheats starting at track cell `idx`, `remaining` being the distance to
+/// the end on the best path through it. All the cells at most `max_iter`
+/// away are looked at in a single scan of the diamond around it.
+fn find_cheats(
+    idx: usize,
+    remaining: i32,
+    from_end: &Distances,
+    (h, w): (i32, i32),
+    max_iter: i32,
+) -> impl Iterator<Item = Cheat> + '_ {
+    let (x1, y1) = (idx as i32 / w, idx as i32 % w);
+    // only the rows of the diamond inside the grid
+    (-max_iter.min(x1)..=max_iter.min(h - 1 - x1)).flat_map(move |dx| {
+        let width = max_iter - dx.abs();
+        let x2 = x1 + dx;
+        (-width.min(y1)..=width.min(w - 1 - y1)).filter_map(move |dy| {
+            let y2 = y1 + dy;
+            let duration = dx.abs() + dy.abs();
+            let dist2 = from_end[(x2 * w + y2) as usize];
+            // is it a shortcut ? ie, do we save some time compared to
+            // the (remaining - dist2) we take on the track
+            let saving = remaining - dist2 - duration;
+            (duration > 1 && dist2 != UNREACHED && saving > 0).then_some(Cheat {
+                start: (x1, y1),
+                end: (x2, y2),
+                duration,
+                saving,
+            })
+        })
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "###############\n\
-                                 #...#...#.....#\n\
-                                 #.#.#.#.#.###.#\n\
-                                 #S#...#.#.#...#\n\
-                                 #######.#.#.###\n\
-                                 #######.#.#...#\n\
-                                 #######.#.###.#\n\
-                                 ###..E#...#...#\n\
-                                 ###.#######.###\n\
-                                 #...###...#...#\n\
-                                 #.#####.#.###.#\n\
-                                 #.#...#.#.#...#\n\
-                                 #.#.#.#.#.#.###\n\
-                                 #...#...#...###\n\
-                                 ###############";
+    const INPUT: &str = "###############\n\
+                         #...#...#.....#\n\
+                         #.#.#.#.#.###.#\n\
+                         #S#...#.#.#...#\n\
+                         #######.#.#.###\n\
+                         #######.#.#...#\n\
+                         #######.#.###.#\n\
+                         ###..E#...#...#\n\
+                         ###.#######.###\n\
+                         #...###...#...#\n\
+                         #.#####.#.###.#\n\
+                         #.#...#.#.#...#\n\
+                         #.#.#.#.#.#.###\n\
+                         #...#...#...###\n\
+                         ###############";
 
     #[test]
     fn part1_example() {
         let (grid, race_info) = parse(INPUT);
-        assert_eq!(solve(&grid, &race_info, 64, 2), 1);
+        assert_eq!(solve(&grid, &race_info, 64, 2).unwrap().0, 1);
     }
 
     #[test]
     fn part2_example() {
         let (grid, race_info) = parse(INPUT);
-        assert_eq!(solve(&grid, &race_info, 72, 20), 29);
+        assert_eq!(solve(&grid, &race_info, 72, 20).unwrap().0, 29);
+    }
+
+    #[test]
+    fn part1_histogram() {
+        let (grid, race_info) = parse(INPUT);
+        let (count, histogram) = solve(&grid, &race_info, 1, 2).unwrap();
+        let expected = BTreeMap::from([
+            (2, 14),
+            (4, 14),
+            (6, 2),
+            (8, 4),
+            (10, 2),
+            (12, 3),
+            (20, 1),
+            (36, 1),
+            (38, 1),
+            (40, 1),
+            (64, 1),
+        ]);
+        assert_eq!(histogram, expected);
+        assert_eq!(count, 44);
+        assert_eq!(
+            format_savings(&histogram, 36),
+            "There is one cheat that saves 36 picoseconds.\n\
+             There is one cheat that saves 38 picoseconds.\n\
+             There is one cheat that saves 40 picoseconds.\n\
+             There is one cheat that saves 64 picoseconds."
+        );
+    }
+
+    #[test]
+    fn part2_histogram() {
+        let (grid, race_info) = parse(INPUT);
+        let (count, histogram) = solve(&grid, &race_info, 50, 20).unwrap();
+        let expected = BTreeMap::from([
+            (50, 32),
+            (52, 31),
+            (54, 29),
+            (56, 39),
+            (58, 25),
+            (60, 23),
+            (62, 20),
+            (64, 19),
+            (66, 12),
+            (68, 14),
+            (70, 12),
+            (72, 22),
+            (74, 4),
+            (76, 3),
+        ]);
+        let above: BTreeMap<i32, usize> = histogram.range(50..).map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(above, expected);
+        assert_eq!(count, expected.values().sum::<usize>());
+        assert!(format_savings(&histogram, 50)
+            .starts_with("There are 32 cheats that save 50 picoseconds.\n"));
+    }
+
+    #[test]
+    fn branching_track() {
+        // the track forks at S: 6 steps down and around to E,
+        // or the long way over the top and down the right side
+        let input = "#######\n\
+                     #.....#\n\
+                     #.###.#\n\
+                     #S#E#.#\n\
+                     #.#.#.#\n\
+                     #.....#\n\
+                     #######";
+        let (grid, race_info) = parse(input);
+        let (count, histogram) = solve(&grid, &race_info, 1, 2).unwrap();
+        // through the wall between S and E, or the one just below it
+        assert_eq!(histogram, BTreeMap::from([(2, 1), (4, 1)]));
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn unreachable_end() {
+        let input = "#######\n\
+                     #S.#.E#\n\
+                     #######";
+        let (grid, race_info) = parse(input);
+        assert_eq!(solve(&grid, &race_info, 1, 2), None);
+    }
+
+    #[test]
+    fn part1_cheat_positions() {
+        let (grid, race_info) = parse(INPUT);
+        let cheats = cheats(&grid, &race_info, 2).unwrap();
+        let best: Vec<&Cheat> = cheats.iter().filter(|c| c.saving == 64).collect();
+        assert_eq!(
+            best,
+            vec![&Cheat {
+                start: (7, 7),
+                end: (7, 5),
+                duration: 2,
+                saving: 64
+            }]
+        );
+        assert_eq!(
+            render_cheat(&grid, best[0]).lines().nth(7),
+            Some("###..21...#...#")
+        );
+    }
+
+    #[test]
+    fn part2_cheat_positions() {
+        let (grid, race_info) = parse(INPUT);
+        let cheats = cheats(&grid, &race_info, 20).unwrap();
+        let best: Vec<&Cheat> = cheats.iter().filter(|c| c.saving == 76).collect();
+        assert_eq!(best.len(), 3);
+        let cheat = Cheat {
+            start: (3, 1),
+            end: (7, 3),
+            duration: 6,
+            saving: 76,
+        };
+        assert!(best.contains(&&cheat));
+        assert_eq!(
+            render_cheat(&grid, &cheat),
+            "###############\n\
+             #...#...#.....#\n\
+             #.#.#.#.#.###.#\n\
+             #S#...#.#.#...#\n\
+             #1#####.#.#.###\n\
+             #2#####.#.#...#\n\
+             #3#####.#.###.#\n\
+             #456.E#...#...#\n\
+             ###.#######.###\n\
+             #...###...#...#\n\
+             #.#####.#.###.#\n\
+             #.#...#.#.#...#\n\
+             #.#.#.#.#.#.###\n\
+             #...#...#...###\n\
+             ###############"
+        );
     }
 }
@@ -181,16 +181,16 @@ fn part2(input: &GridA) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "MMMSXXMASM\n\
-                                 MSAMXMSMSA\n\
-                                 AMXSXMAAMM\n\
-                                 MSAMASMSMX\n\
-                                 XMASAMXAMM\n\
-                                 XXAMMXXAMA\n\
-                                 SMSMSASXSS\n\
-                                 SAXAMASAAA\n\
-                                 MAMMMXMMMM\n\
-                                 MXMXAXMASX";
+    const INPUT: &str = "MMMSXXMASM\n\
+                         MSAMXMSMSA\n\
+                         AMXSXMAAMM\n\
+                         MSAMASMSMX\n\
+                         XMASAMXAMM\n\
+                         XXAMMXXAMA\n\
+                         SMSMSASXSS\n\
+                         SAXAMASAAA\n\
+                         MAMMMXMMMM\n\
+                         MXMXAXMASX";
 
     #[test]
     fn part1_example() {
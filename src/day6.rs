@@ -53,11 +53,8 @@ fn solve1((map, guard_pos): &(Map, (i32, i32))) -> HashSet<(i32, i32)> {
     let mut visited: HashSet<(i32, i32)> = HashSet::new();
     visited.insert((curr_pos.0, curr_pos.1));
 
-    loop {
-        let Some((next_pos, is_obstacle)) = get_next_pos(&curr_pos, direction, map) else {
-            // guard went out of the map
-            break;
-        };
+    // stop once the guard goes out of the map
+    while let Some((next_pos, is_obstacle)) = get_next_pos(&curr_pos, direction, map) {
         // check if next move will throw us into an obstacle
         if !is_obstacle {
             // no obstacle, we can proceed
@@ -80,11 +77,8 @@ fn solve2((map, guard_pos): &(Map, (i32, i32))) -> usize {
     // keep track of visited positions and directions
     let mut visited: HashSet<(i32, i32, i32, i32)> = HashSet::new();
 
-    loop {
-        let Some((next_pos, is_obstacle)) = get_next_pos(&curr_pos, direction, map) else {
-            // guard went out of the map
-            break;
-        };
+    // stop once the guard goes out of the map
+    while let Some((next_pos, is_obstacle)) = get_next_pos(&curr_pos, direction, map) {
         // check if next move will throw us into an obstacle
         if !is_obstacle {
             // no obstacle, we can proceed
@@ -126,16 +120,16 @@ fn part2((map, guard_pos): &(Map, (i32, i32))) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "....#.....\n\
-                                 .........#\n\
-                                 ..........\n\
-                                 ..#.......\n\
-                                 .......#..\n\
-                                 ..........\n\
-                                 .#..^.....\n\
-                                 ........#.\n\
-                                 #.........\n\
-                                 ......#...";
+    const INPUT: &str = "....#.....\n\
+                         .........#\n\
+                         ..........\n\
+                         ..#.......\n\
+                         .......#..\n\
+                         ..........\n\
+                         .#..^.....\n\
+                         ........#.\n\
+                         #.........\n\
+                         ......#...";
 
     #[test]
     fn part1_example() {
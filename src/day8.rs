@@ -119,18 +119,18 @@ fn part2(input: &(Antennas, usize)) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "............\n\
-                                 ........0...\n\
-                                 .....0......\n\
-                                 .......0....\n\
-                                 ....0.......\n\
-                                 ......A.....\n\
-                                 ............\n\
-                                 ............\n\
-                                 ........A...\n\
-                                 .........A..\n\
-                                 ............\n\
-                                 ............";
+    const INPUT: &str = "............\n\
+                         ........0...\n\
+                         .....0......\n\
+                         .......0....\n\
+                         ....0.......\n\
+                         ......A.....\n\
+                         ............\n\
+                         ............\n\
+                         ........A...\n\
+                         .........A..\n\
+                         ............\n\
+                         ............";
 
     #[test]
     fn part1_example() {
@@ -116,7 +116,7 @@ fn part2(input: &[(usize, usize)]) -> usize {
 mod tests {
     use super::*;
 
-    const INPUT: &'static str = "2333133121414131402";
+    const INPUT: &str = "2333133121414131402";
 
     #[test]
     fn part1_example() {
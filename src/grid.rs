@@ -0,0 +1,7 @@
+/// Write a grid of characters back the way it is given in the input
+pub fn grid_to_string(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
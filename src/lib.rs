@@ -1,15 +1,15 @@
-mod day20;
-mod day19;
-mod day18;
-mod day17;
-mod day16;
+pub mod day20;
+pub mod day19;
+pub mod day18;
+pub mod day17;
+pub mod day16;
 mod day1;
 mod day10;
 mod day11;
-mod day12;
+pub mod day12;
 mod day13;
 mod day14;
-mod day15;
+pub mod day15;
 mod day2;
 mod day3;
 mod day4;
@@ -18,6 +18,7 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+pub mod grid;
 use aoc_runner_derive::aoc_lib;
 
 aoc_lib! { year = 2024 }